@@ -0,0 +1,58 @@
+//! Compares the `BitGrid` implementations on the kind of work the region solver does: finding the
+//! overlap between each revealed number's neighborhood and its neighbors', and splitting them.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use minesweeper::bitgrid::{ArrayBitGrid, BitGrid, BitVecBitGrid, ChunkBitGrid};
+
+const SIZES: [(usize, usize); 3] = [(9, 9), (30, 16), (100, 100)];
+
+/// The 3x3 neighborhood around every cell.
+fn neighborhoods<G: BitGrid>(size: (usize, usize)) -> Vec<G> {
+    let mut grids = Vec::new();
+    for x in 0..size.0 {
+        for y in 0..size.1 {
+            let cells = (x.saturating_sub(1)..(x + 2).min(size.0))
+                .flat_map(|nx| (y.saturating_sub(1)..(y + 2).min(size.1)).map(move |ny| (nx, ny)));
+            grids.push(G::empty(size).with_indices(cells));
+        }
+    }
+    grids
+}
+
+/// Splits each neighborhood against the next few, like `Region::split_overlap`.
+fn split_all<G: BitGrid>(grids: &[G]) -> usize {
+    let mut total = 0;
+    for (i, a) in grids.iter().enumerate() {
+        for b in grids.iter().skip(i + 1).take(4) {
+            let overlap = a.and(b);
+            if overlap.size() == 0 {
+                continue;
+            }
+            total += a.and_not(b).size() + b.and_not(a).size() + a.or(b).size();
+            total += overlap.indices().count();
+        }
+    }
+    total
+}
+
+fn bench_grid<G: BitGrid>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group("split_overlap");
+    for size in SIZES {
+        let grids = neighborhoods::<G>(size);
+        group.bench_with_input(
+            BenchmarkId::new(name, format!("{}x{}", size.0, size.1)),
+            &grids,
+            |b, grids| b.iter(|| split_all(grids)),
+        );
+    }
+    group.finish();
+}
+
+fn bitgrids(c: &mut Criterion) {
+    bench_grid::<ArrayBitGrid>(c, "ndarray");
+    bench_grid::<BitVecBitGrid>(c, "bitvec");
+    bench_grid::<ChunkBitGrid>(c, "u64");
+}
+
+criterion_group!(benches, bitgrids);
+criterion_main!(benches);
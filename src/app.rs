@@ -1,16 +1,47 @@
 use std::time::{Duration, Instant};
 
 use egui::{
-    emath::Rot2, vec2, Align2, Color32, DragValue, FontId, Key, Rounding, Sense, Shape, Stroke,
-    Vec2, Widget,
+    vec2, Align2, Color32, DragValue, FontId, PointerButton, Rect, Rounding, Sense, Slider, Stroke,
+    Vec2, Widget, WidgetInfo, WidgetType,
 };
+use itertools::Itertools;
 use ndarray::Array2;
 
 use crate::{
-    game::{Cell, CellState, Field},
-    solver::{predict, Prediction},
+    difficulty::{Difficulty, Rating},
+    game::{Cell, CellState, Density, Field, Rules, MAX_MINES_PER_CELL},
+    keybindings::{Action, KeyBindings},
+    puzzle::{Goal, Puzzle, Round},
+    solver::{recommend_guess, Prediction, Solver, SolverChoice},
+    theme::{self, OverlayStyle, Theme, ThemeChoice, TileStyle},
 };
 
+/// How many boards to try generating before giving up on finding one within the difficulty limits.
+const DIFFICULTY_LIMIT_ATTEMPTS: usize = 20;
+/// How long rating the board can take each frame, so that big boards don't freeze the game.
+const RATING_FRAME_BUDGET: Duration = Duration::from_millis(10);
+
+/// How many moves can be undone.
+const UNDO_LIMIT: usize = 100;
+/// The smallest cells are allowed to get before the field needs to be scrolled instead.
+const MIN_CELL_SIZE: f32 = 16.0;
+/// The smallest cells are allowed to get on touch screens.
+const MIN_TOUCH_CELL_SIZE: f32 = 32.0;
+/// How long a cell has to be held (in seconds) to flag it. This needs to be shorter than egui's
+/// maximum click duration, so that the press does not count as a click first.
+const LONG_PRESS_DURATION: f64 = 0.4;
+/// The largest zoom, relative to fitting the whole field in view.
+const MAX_ZOOM: f32 = 8.0;
+/// The length of the longest side of the minimap.
+const MINIMAP_SIZE: f32 = 150.0;
+const MINIMAP_MARGIN: f32 = 8.0;
+/// The cell size the window is resized for when fitting it to a new board.
+const FIT_CELL_SIZE: f32 = 24.0;
+/// How much of the monitor the window can take up when fitting it to a new board.
+const FIT_MONITOR_FRACTION: f32 = 0.9;
+/// The space taken up by the window's title bar and borders, if the window system doesn't say.
+const FIT_DECORATION_MARGIN: Vec2 = vec2(16.0, 48.0);
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
@@ -21,12 +52,166 @@ pub struct Minesweeper {
     game_over: bool,
     new_field_size: (usize, usize),
     new_field_mines: usize,
+    new_field_rules: Rules,
+    /// The weights painted for [`Density::Painted`], the same size as new fields
+    density_mask: Array2<f32>,
+    #[serde(skip)]
+    show_density_mask: bool,
+    /// How heavily the density mask is painted
+    density_brush: f32,
     #[serde(skip)]
     selected: Option<(usize, usize)>,
     #[serde(skip)]
     predictions: Option<Array2<Option<Prediction>>>,
     #[serde(skip)]
-    last_predictions_time: Option<Duration>,
+    recommended_guess: Option<(usize, usize)>,
+    /// How long each solver took the last time it ran, indexed by [`SolverChoice`]
+    #[serde(skip)]
+    solver_times: [Option<Duration>; SolverChoice::ALL.len()],
+    solver: SolverChoice,
+    /// A summary of how the solvers' predictions differ, from the last comparison
+    #[serde(skip)]
+    solver_comparison: Option<String>,
+    #[serde(skip)]
+    difficulty: Option<Difficulty>,
+    /// The rating of the board that is still being played out, and how many more boards can be
+    /// generated if it falls outside of the difficulty limits
+    #[serde(skip)]
+    rating: Option<(Rating, usize)>,
+    show_difficulty_during_game: bool,
+    limit_difficulty: bool,
+    difficulty_limits: (f32, f32),
+    zoom: f32,
+    /// The field's view rect and scroll offset from the last frame
+    #[serde(skip)]
+    last_field_view: Option<(Rect, Vec2)>,
+    #[serde(skip)]
+    pending_scroll_offset: Option<Vec2>,
+    tap_mode: TapMode,
+    long_press_to_flag: bool,
+    /// The cell that was flagged by the current long press, so releasing it doesn't also clear it
+    #[serde(skip)]
+    long_pressed: Option<(usize, usize)>,
+    /// Whether the current press came from a touch screen, since only those can be long-pressed
+    #[serde(skip)]
+    touch_press: bool,
+    chord_triggers: ChordTriggers,
+    /// Whether chording a number flags its hidden neighbors when they must all be mines
+    flag_chord: bool,
+    /// Playing without flags: flagging is turned off, and chording counts the mines the solver can
+    /// infer instead
+    no_flag: bool,
+    stats: Stats,
+    /// Whether a flag has been placed in this game, so a win doesn't count as a no-flag win
+    #[serde(skip)]
+    used_flags: bool,
+    /// Whether this game's result has been counted in the stats
+    #[serde(skip)]
+    game_recorded: bool,
+    /// The cell under the pointer when both buttons were last held down together, which is chorded
+    /// once they are released
+    #[serde(skip)]
+    both_pressed: Option<(usize, usize)>,
+    #[serde(skip)]
+    touch_detected: bool,
+    /// Whether to resize the window to fit the board when a new game starts (native only)
+    fit_window: bool,
+    /// Set when a new game starts, so the window is fitted once the board's area is known
+    #[serde(skip)]
+    pending_fit_window: bool,
+    /// Snapshots of the game from before each move
+    #[serde(skip)]
+    history: Vec<Snapshot>,
+    key_bindings: KeyBindings,
+    #[serde(skip)]
+    show_key_bindings: bool,
+    /// The action that the next key press will be bound to
+    #[serde(skip)]
+    capturing_key: Option<Action>,
+    theme_choice: ThemeChoice,
+    custom_theme: Theme,
+    #[serde(skip)]
+    show_theme: bool,
+    flag_mines_on_win: bool,
+    /// Whether marking a flagged cell again gives it a question mark
+    question_marks: bool,
+    overlay_style: OverlayStyle,
+    /// A description of the last thing that happened, for screen readers
+    #[serde(skip)]
+    status: String,
+    /// The puzzle being built in the editor
+    editor_puzzle: Puzzle,
+    /// The starting field of the puzzle in the editor, built again whenever the puzzle is edited
+    #[serde(skip)]
+    editor_field: Option<Field>,
+    #[serde(skip)]
+    editor_tool: EditorTool,
+    /// The result of the last solvability check of the puzzle in the editor
+    #[serde(skip)]
+    editor_check: Option<Difficulty>,
+    #[serde(skip)]
+    show_editor: bool,
+    puzzles: Vec<Puzzle>,
+    /// The puzzle mode round being played, if any
+    #[serde(skip)]
+    puzzle_round: Option<Round>,
+    puzzle_goal: Goal,
+}
+
+/// Which clicks chord a revealed number (clear its neighbors, if it has enough flags around it).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct ChordTriggers {
+    /// Clicking (or tapping) the number itself
+    number_click: bool,
+    /// Pressing both mouse buttons together, and releasing them
+    both_buttons: bool,
+    middle_click: bool,
+}
+
+impl Default for ChordTriggers {
+    fn default() -> Self {
+        Self {
+            number_click: true,
+            both_buttons: true,
+            middle_click: true,
+        }
+    }
+}
+
+/// The results of every game played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct Stats {
+    wins: usize,
+    /// Wins without placing a single flag
+    no_flag_wins: usize,
+    losses: usize,
+}
+
+/// The state of a game from before a move, so that the move can be undone.
+#[derive(Clone, Debug)]
+struct Snapshot {
+    field: Field,
+    game_over: bool,
+    /// The stats, and whether the game had been counted in them, so that undoing the move that
+    /// ended the game takes its result back out
+    stats: Stats,
+    game_recorded: bool,
+}
+
+/// What clicking a cell in the puzzle editor does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EditorTool {
+    Mine,
+    Reveal,
+}
+
+/// What a primary click or tap on an unrevealed cell does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+enum TapMode {
+    Reveal,
+    Flag,
 }
 
 impl Default for Minesweeper {
@@ -34,14 +219,62 @@ impl Default for Minesweeper {
         let new_field_size = (25, 25);
         let new_field_mines = 40;
         Self {
-            field: Field::new(new_field_size, new_field_mines)
+            field: Field::new(new_field_size, new_field_mines, Rules::default())
                 .expect("initializing field using fixed values"),
             game_over: false,
             new_field_size,
             new_field_mines,
+            new_field_rules: Rules::default(),
+            density_mask: Array2::from_elem(new_field_size, 1.0),
+            show_density_mask: false,
+            density_brush: 1.0,
             selected: None,
             predictions: None,
-            last_predictions_time: None,
+            recommended_guess: None,
+            solver_times: [None; SolverChoice::ALL.len()],
+            solver: SolverChoice::Regions,
+            solver_comparison: None,
+            difficulty: None,
+            rating: None,
+            show_difficulty_during_game: false,
+            limit_difficulty: false,
+            difficulty_limits: (0.0, 20.0),
+            zoom: 1.0,
+            last_field_view: None,
+            pending_scroll_offset: None,
+            tap_mode: TapMode::Reveal,
+            long_press_to_flag: true,
+            long_pressed: None,
+            touch_press: false,
+            chord_triggers: ChordTriggers::default(),
+            flag_chord: false,
+            no_flag: false,
+            stats: Stats::default(),
+            used_flags: false,
+            game_recorded: false,
+            both_pressed: None,
+            touch_detected: false,
+            fit_window: true,
+            pending_fit_window: false,
+            history: Vec::new(),
+            key_bindings: KeyBindings::default(),
+            show_key_bindings: false,
+            capturing_key: None,
+            theme_choice: ThemeChoice::Egui,
+            custom_theme: Theme::classic(),
+            show_theme: false,
+            flag_mines_on_win: true,
+            question_marks: false,
+            overlay_style: OverlayStyle::Colors,
+            status: String::new(),
+            editor_puzzle: Puzzle::new((9, 9)),
+            editor_field: None,
+            editor_tool: EditorTool::Mine,
+            editor_check: None,
+            show_editor: false,
+            puzzles: Vec::new(),
+            puzzle_round: None,
+            puzzle_goal: Goal::AllSafe,
         }
     }
 }
@@ -54,11 +287,787 @@ impl Minesweeper {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
         if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            let mut app: Self = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            app.key_bindings.add_missing_defaults();
+            return app;
         }
 
         Default::default()
     }
+
+    fn new_game(&mut self) {
+        if let Some(field) = Field::new(
+            self.new_field_size,
+            self.new_field_mines,
+            self.new_field_rules,
+        ) {
+            // Only a board of a different size needs a different window
+            self.pending_fit_window = self.fit_window && field.size() != self.field.size();
+            self.field = field.with_mask(self.density_mask.clone());
+            self.puzzle_round = None;
+            self.game_over = false;
+            self.used_flags = false;
+            self.game_recorded = false;
+            self.difficulty = None;
+            self.rating = None;
+            self.history.clear();
+            self.refresh_predictions();
+        }
+    }
+
+    /// Starts rating the board once it has been clicked. See [`Self::continue_rating`].
+    fn rate_difficulty(&mut self) {
+        if self.difficulty.is_none() && self.rating.is_none() {
+            self.rating =
+                Rating::start(&self.field).map(|rating| (rating, DIFFICULTY_LIMIT_ATTEMPTS));
+        }
+    }
+
+    /// Plays out the board's rating for up to [`RATING_FRAME_BUDGET`], regenerating the board (with
+    /// the same first click) if it falls outside of the difficulty limits.
+    fn continue_rating(&mut self, ctx: &egui::Context) {
+        let Some((rating, attempts_left)) = self.rating.as_mut() else {
+            return;
+        };
+
+        let t0 = Instant::now();
+        let difficulty = loop {
+            if let Some(difficulty) = rating.step() {
+                break difficulty;
+            }
+            if t0.elapsed() >= RATING_FRAME_BUDGET {
+                ctx.request_repaint();
+                return;
+            }
+        };
+
+        let (min, max) = self.difficulty_limits;
+        if self.limit_difficulty && *attempts_left > 0 && !(min..=max).contains(&difficulty.score())
+        {
+            *attempts_left -= 1;
+            self.field.regenerate();
+            *rating = Rating::start(&self.field).expect("regenerated field has been clicked");
+            self.refresh_predictions();
+            ctx.request_repaint();
+        } else {
+            self.difficulty = Some(difficulty);
+            self.rating = None;
+        }
+    }
+
+    /// Whether the board might still be regenerated to fit the difficulty limits, so it can't be
+    /// played yet.
+    fn generating(&self) -> bool {
+        self.limit_difficulty && self.rating.is_some()
+    }
+
+    /// Draws an overview of the whole field in the corner of the view, with the visible part
+    /// outlined. Returns the scroll offset to jump to if the minimap was clicked or dragged.
+    fn show_minimap(
+        &self,
+        ui: &mut egui::Ui,
+        view_rect: Rect,
+        offset: Vec2,
+        cell_size: f32,
+    ) -> Option<Vec2> {
+        let (width, height) = self.field.size();
+        let scale = MINIMAP_SIZE / width.max(height) as f32;
+        let size = vec2(width as f32, height as f32) * scale;
+        let rect = Rect::from_min_size(view_rect.max - size - Vec2::splat(MINIMAP_MARGIN), size);
+        let response = ui.interact(rect, ui.id().with("minimap"), Sense::click_and_drag());
+
+        let painter = ui.painter_at(rect.expand(1.0));
+        painter.rect_filled(rect, Rounding::ZERO, ui.visuals().extreme_bg_color);
+        for (pos, cell) in self.field.board.indexed_iter() {
+            let color = match cell.state {
+                CellState::Unrevealed | CellState::Questioned => continue,
+                CellState::Flagged => Color32::RED,
+                CellState::Exploded => Color32::YELLOW,
+                CellState::Revealed | CellState::Empty => ui.visuals().widgets.inactive.bg_fill,
+            };
+            painter.rect_filled(
+                Rect::from_min_size(
+                    rect.min + vec2(pos.0 as f32, pos.1 as f32) * scale,
+                    Vec2::splat(scale),
+                ),
+                Rounding::ZERO,
+                color,
+            );
+        }
+        let visible = Rect::from_min_size(
+            rect.min + offset / cell_size * scale,
+            view_rect.size() / cell_size * scale,
+        );
+        painter.rect_stroke(
+            visible.intersect(rect),
+            Rounding::ZERO,
+            (1.0, Color32::BLUE),
+        );
+        painter.rect_stroke(rect, Rounding::ZERO, ui.visuals().window_stroke());
+
+        let pointer = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked() || response.dragged())?;
+        Some((pointer - rect.min) / scale * cell_size - view_rect.size() / 2.0)
+    }
+
+    fn undo(&mut self) {
+        if let Some(snapshot) = self.history.pop() {
+            self.field = snapshot.field;
+            self.game_over = snapshot.game_over;
+            self.stats = snapshot.stats;
+            self.game_recorded = snapshot.game_recorded;
+            if self.field.first_click().is_none() {
+                self.difficulty = None;
+                self.rating = None;
+            }
+            self.refresh_predictions();
+        }
+    }
+
+    /// Selects a cell that is known to be free, or the recommended guess if there are none.
+    fn hint(&mut self) {
+        if self.puzzle_round.is_some() {
+            return;
+        }
+        let probabilities = self.solver.solver().predict(&self.field);
+        let free = probabilities
+            .indexed_iter()
+            .find(|(_, prob)| **prob == Some(0.0))
+            .map(|(pos, _)| pos);
+        if let Some(pos) = free.or_else(|| recommend_guess(&self.field, &probabilities)) {
+            self.selected = Some(pos);
+        }
+    }
+
+    fn set_show_predictions(&mut self, show: bool) {
+        if show {
+            let (preds, recommended, t) = self.field.get_predictions(self.solver.solver());
+            self.predictions = Some(preds);
+            self.recommended_guess = recommended;
+            self.solver_times[self.solver as usize] = Some(t);
+        } else {
+            self.predictions = None;
+            self.recommended_guess = None;
+        }
+    }
+
+    fn key_bindings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_key_bindings;
+        egui::Window::new("Key Bindings")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                egui::Grid::new("key_bindings")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for action in Action::ALL {
+                            ui.label(action.name());
+                            ui.horizontal(|ui| {
+                                for key in self.key_bindings.keys(action).to_vec() {
+                                    if ui
+                                        .button(key.symbol_or_name())
+                                        .on_hover_text("Click to remove")
+                                        .clicked()
+                                    {
+                                        self.key_bindings.unbind(action, key);
+                                    }
+                                }
+                                if self.capturing_key == Some(action) {
+                                    ui.label("Press a key");
+                                    if ui.button("Cancel").clicked() {
+                                        self.capturing_key = None;
+                                    }
+                                } else if ui.button("+").clicked() {
+                                    self.capturing_key = Some(action);
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+                if ui.button("Reset to defaults").clicked() {
+                    self.key_bindings = Default::default();
+                }
+            });
+        self.show_key_bindings = open;
+        if !open {
+            self.capturing_key = None;
+        }
+
+        if let Some(action) = self.capturing_key {
+            let key = ctx.input(|inp| {
+                inp.events.iter().find_map(|event| match event {
+                    egui::Event::Key {
+                        key, pressed: true, ..
+                    } => Some(*key),
+                    _ => None,
+                })
+            });
+            if let Some(key) = key {
+                self.key_bindings.bind(action, key);
+                self.capturing_key = None;
+            }
+        }
+    }
+
+    fn theme(&self, visuals: &egui::Visuals) -> Theme {
+        match self.theme_choice {
+            ThemeChoice::Egui => Theme::from_visuals(visuals),
+            ThemeChoice::Classic => Theme::classic(),
+            ThemeChoice::Custom => self.custom_theme,
+        }
+    }
+
+    fn theme_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Theme")
+            .open(&mut self.show_theme)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for choice in ThemeChoice::ALL {
+                        ui.selectable_value(&mut self.theme_choice, choice, choice.name());
+                    }
+                });
+
+                if self.theme_choice != ThemeChoice::Custom {
+                    if ui.button("Customize").clicked() {
+                        let current = match self.theme_choice {
+                            ThemeChoice::Egui => Theme::from_visuals(ui.visuals()),
+                            _ => Theme::classic(),
+                        };
+                        self.custom_theme = current;
+                        self.theme_choice = ThemeChoice::Custom;
+                    }
+                } else {
+                    let theme = &mut self.custom_theme;
+                    ui.horizontal(|ui| {
+                        ui.label("Tiles:");
+                        ui.selectable_value(&mut theme.tiles, TileStyle::Flat, "Flat");
+                        ui.selectable_value(&mut theme.tiles, TileStyle::Raised, "Raised");
+                    });
+                    egui::Grid::new("theme_colors").show(ui, |ui| {
+                        for (label, color) in [
+                            ("Hidden", &mut theme.hidden_fill),
+                            ("Revealed", &mut theme.revealed_fill),
+                            ("Exploded", &mut theme.exploded_fill),
+                            ("Border", &mut theme.border),
+                            ("Text", &mut theme.text),
+                            ("Flag", &mut theme.flag),
+                            ("Mine", &mut theme.mine),
+                            ("Selected", &mut theme.selected),
+                            ("Hovered", &mut theme.hovered),
+                        ] {
+                            ui.label(label);
+                            ui.color_edit_button_srgba(color);
+                            ui.end_row();
+                        }
+                        ui.label("Numbers");
+                        ui.horizontal(|ui| {
+                            for color in &mut theme.numbers {
+                                ui.color_edit_button_srgba(color);
+                            }
+                        });
+                        ui.end_row();
+                    });
+                }
+
+                ui.separator();
+                let theme = match self.theme_choice {
+                    ThemeChoice::Egui => Theme::from_visuals(ui.visuals()),
+                    ThemeChoice::Classic => Theme::classic(),
+                    ThemeChoice::Custom => self.custom_theme,
+                };
+                theme_preview(ui, theme);
+            });
+    }
+
+    fn density_mask_window(&mut self, ctx: &egui::Context) {
+        const MAX_SIZE: f32 = 400.0;
+
+        if self.density_mask.dim() != self.new_field_size {
+            self.density_mask = Array2::from_elem(self.new_field_size, 1.0);
+        }
+        egui::Window::new("Density Mask")
+            .open(&mut self.show_density_mask)
+            .show(ctx, |ui| {
+                ui.label("Mines are more likely in darker cells");
+                ui.horizontal(|ui| {
+                    ui.add(Slider::new(&mut self.density_brush, 0.0..=1.0).text("Brush"));
+                    if ui.button("Fill").clicked() {
+                        self.density_mask.fill(self.density_brush);
+                    }
+                });
+
+                let (width, height) = self.density_mask.dim();
+                let cell_size = (MAX_SIZE / width.max(height) as f32).min(16.0);
+                let (response, painter) = ui.allocate_painter(
+                    vec2(width as f32, height as f32) * cell_size,
+                    Sense::click_and_drag(),
+                );
+                let rect = response.rect;
+                if let Some(pointer) = response
+                    .interact_pointer_pos()
+                    .filter(|_| response.clicked() || response.dragged())
+                {
+                    let offset = (pointer - rect.min) / cell_size;
+                    let pos = (offset.x as usize, offset.y as usize);
+                    if let Some(weight) = self.density_mask.get_mut(pos) {
+                        *weight = self.density_brush;
+                    }
+                }
+
+                let stroke = ui.visuals().widgets.noninteractive.bg_stroke;
+                for ((x, y), weight) in self.density_mask.indexed_iter() {
+                    let cell = Rect::from_min_size(
+                        rect.min + vec2(x as f32, y as f32) * cell_size,
+                        Vec2::splat(cell_size),
+                    );
+                    let shade = 255 - (weight.clamp(0.0, 1.0) * 255.0) as u8;
+                    painter.rect(cell, Rounding::ZERO, Color32::from_gray(shade), stroke);
+                }
+            });
+    }
+
+    /// Starts playing a puzzle, with its difficulty already known.
+    fn play_puzzle(&mut self, puzzle: &Puzzle) {
+        self.pending_fit_window = self.fit_window && puzzle.size != self.field.size();
+        self.field = puzzle.field();
+        self.puzzle_round = None;
+        self.used_flags = false;
+        self.game_recorded = false;
+        self.game_over = false;
+        self.difficulty = Some(puzzle.difficulty());
+        self.rating = None;
+        self.history.clear();
+        self.refresh_predictions();
+    }
+
+    /// Plays a puzzle in puzzle mode, picking safe cells instead of revealing them.
+    fn start_round(&mut self, puzzle: &Puzzle) {
+        self.play_puzzle(puzzle);
+        self.puzzle_round = Some(Round::new(&self.field, self.puzzle_goal));
+        self.status = format!("{}, without guessing", self.puzzle_goal.name());
+    }
+
+    /// The prediction to show on a cell, and whether to circle it. While a puzzle round is being
+    /// played, this shows the picks instead, and once it has been submitted, which picks were
+    /// right (with the missed safe cells circled).
+    fn cell_overlay(&self, pos: (usize, usize)) -> (Option<Prediction>, bool) {
+        match &self.puzzle_round {
+            Some(round) => {
+                let picked = round.picks.contains(&pos);
+                match round.score {
+                    None => (picked.then_some(Prediction::Free), false),
+                    Some(_) if picked && round.is_safe(pos) => (Some(Prediction::Free), false),
+                    Some(_) if picked => (Some(Prediction::Mine), false),
+                    Some(_) => (None, round.is_safe(pos)),
+                }
+            }
+            None => (
+                self.predictions
+                    .as_ref()
+                    .and_then(|predictions| predictions[pos]),
+                self.recommended_guess == Some(pos),
+            ),
+        }
+    }
+
+    fn editor_window(&mut self, ctx: &egui::Context) {
+        const CELL_SIZE: f32 = 20.0;
+
+        let mut open = self.show_editor;
+        egui::Window::new("Puzzle Editor")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let theme = self.theme(ui.visuals());
+                let puzzle = &mut self.editor_puzzle;
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut puzzle.name);
+                });
+                let mut size = puzzle.size;
+                ui.horizontal(|ui| {
+                    ui.label("Size:");
+                    ui.add(DragValue::new(&mut size.0).clamp_range(1..=100));
+                    ui.label("by");
+                    ui.add(DragValue::new(&mut size.1).clamp_range(1..=100));
+                });
+                if size != puzzle.size {
+                    puzzle.resize(size);
+                    self.editor_check = None;
+                    self.editor_field = None;
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Click to:");
+                    ui.selectable_value(&mut self.editor_tool, EditorTool::Mine, "Place mines");
+                    ui.selectable_value(&mut self.editor_tool, EditorTool::Reveal, "Reveal");
+                });
+
+                let field = self.editor_field.get_or_insert_with(|| puzzle.field());
+                let mut clicked = None;
+                egui::ScrollArea::both().max_height(400.0).show(ui, |ui| {
+                    ui.spacing_mut().item_spacing = Vec2::ZERO;
+                    for y in 0..puzzle.size.1 {
+                        ui.horizontal(|ui| {
+                            for x in 0..puzzle.size.0 {
+                                let response = ui.add(
+                                    field.board[(x, y)]
+                                        .show(CELL_SIZE, false, None, false, theme, true)
+                                        .position((x, y)),
+                                );
+                                if response.clicked() {
+                                    clicked = Some((x, y));
+                                }
+                            }
+                        });
+                    }
+                });
+                if let Some(pos) = clicked {
+                    match self.editor_tool {
+                        EditorTool::Mine => puzzle.toggle_mine(pos),
+                        EditorTool::Reveal => puzzle.toggle_revealed(pos),
+                    }
+                    self.editor_check = None;
+                    self.editor_field = None;
+                }
+                ui.label(format!("Mines: {}", puzzle.mines.len()));
+
+                ui.horizontal(|ui| {
+                    if ui.button("Check").clicked() {
+                        self.editor_check = Some(puzzle.difficulty());
+                    }
+                    let solvable = self
+                        .editor_check
+                        .is_some_and(|difficulty| difficulty.guesses == 0);
+                    if ui
+                        .add_enabled(
+                            solvable && !puzzle.name.is_empty(),
+                            egui::Button::new("Save"),
+                        )
+                        .on_disabled_hover_text(
+                            "Give the puzzle a name, and check that it can be solved",
+                        )
+                        .clicked()
+                    {
+                        match self
+                            .puzzles
+                            .iter_mut()
+                            .find(|saved| saved.name == puzzle.name)
+                        {
+                            Some(saved) => *saved = puzzle.clone(),
+                            None => self.puzzles.push(puzzle.clone()),
+                        }
+                    }
+                    if ui.button("Clear").clicked() {
+                        *puzzle = Puzzle::new(puzzle.size);
+                        self.editor_check = None;
+                        self.editor_field = None;
+                    }
+                });
+                match self.editor_check {
+                    Some(difficulty) if difficulty.guesses == 0 => {
+                        ui.label(format!(
+                            "Solvable without guessing, difficulty {:.1}",
+                            difficulty.score()
+                        ));
+                    }
+                    Some(difficulty) => {
+                        ui.label(format!(
+                            "Not solvable, needs {} guesses",
+                            difficulty.guesses
+                        ));
+                    }
+                    None => {}
+                }
+
+                ui.separator();
+                ui.label("Saved puzzles:");
+                let mut play = None;
+                let mut remove = None;
+                for (i, saved) in self.puzzles.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(&saved.name);
+                        if ui.button("Play").clicked() {
+                            play = Some(saved.clone());
+                        }
+                        if ui.button("Edit").clicked() {
+                            self.editor_puzzle = saved.clone();
+                            self.editor_check = None;
+                            self.editor_field = None;
+                        }
+                        if ui.button("Delete").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    self.puzzles.remove(i);
+                }
+                if let Some(puzzle) = play {
+                    self.play_puzzle(&puzzle);
+                }
+            });
+        self.show_editor = open;
+    }
+
+    /// Shows the goal of the puzzle round being played, and its score once submitted.
+    fn puzzle_round_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(round) = self.puzzle_round.as_mut() else {
+            return;
+        };
+        ui.separator();
+        ui.label(format!("Puzzle: {}", round.goal.name()));
+        match round.score {
+            None => {
+                ui.label(format!("Picked: {}", round.picks.len()));
+                if ui
+                    .add_enabled(!round.picks.is_empty(), egui::Button::new("Submit"))
+                    .clicked()
+                {
+                    let score = round.submit();
+                    self.status = if score.passed() {
+                        "Correct, well done".to_owned()
+                    } else {
+                        format!(
+                            "{} correct, {} wrong, {} missed",
+                            score.correct, score.wrong, score.missed
+                        )
+                    };
+                }
+            }
+            Some(score) => {
+                ui.label(format!(
+                    "Correct: {}, wrong: {}, missed: {}",
+                    score.correct, score.wrong, score.missed
+                ));
+                ui.label(if score.passed() {
+                    "Solved!"
+                } else {
+                    "Circled cells could be proven safe"
+                });
+                if ui.button("Keep Playing").clicked() {
+                    self.puzzle_round = None;
+                }
+            }
+        }
+        ui.separator();
+    }
+
+    /// Resizes the window so that the board fits at [`FIT_CELL_SIZE`], keeping the space around the
+    /// board the same. `board_area` is the space the board has now. The window, including its
+    /// title bar and borders, is kept within [`FIT_MONITOR_FRACTION`] of the monitor. Does nothing
+    /// on the web, or if the window is maximized or fullscreen.
+    fn resize_to_fit(&self, ctx: &egui::Context, board_area: Vec2) {
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+        let (window, monitor, decorations, fixed) = ctx.input(|inp| {
+            let viewport = inp.viewport();
+            let decorations = viewport
+                .outer_rect
+                .zip(viewport.inner_rect)
+                .map_or(FIT_DECORATION_MARGIN, |(outer, inner)| {
+                    outer.size() - inner.size()
+                });
+            (
+                inp.screen_rect().size(),
+                viewport.monitor_size,
+                decorations,
+                viewport.maximized == Some(true) || viewport.fullscreen == Some(true),
+            )
+        });
+        if fixed {
+            return;
+        }
+
+        let (width, height) = self.field.size();
+        let mut size = window - board_area + vec2(width as f32, height as f32) * FIT_CELL_SIZE;
+        if let Some(monitor) = monitor {
+            size = size.min(monitor * FIT_MONITOR_FRACTION - decorations);
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+    }
+
+    fn toggle_fullscreen(&self, ctx: &egui::Context) {
+        let fullscreen = ctx.input(|inp| inp.viewport().fullscreen.unwrap_or(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(!fullscreen));
+    }
+
+    /// Counts the game in the stats once it has been won or lost.
+    fn record_result(&mut self) {
+        if self.game_recorded {
+            return;
+        }
+        if self.game_over {
+            self.stats.losses += 1;
+        } else if self.field.complete() {
+            self.stats.wins += 1;
+            if !self.used_flags {
+                self.stats.no_flag_wins += 1;
+            }
+        } else {
+            return;
+        }
+        self.game_recorded = true;
+    }
+
+    /// Flags, clears and chords the given cells, in that order, as one move that can be undone.
+    fn make_moves(
+        &mut self,
+        flagged: Vec<(usize, usize)>,
+        cleared: Vec<(usize, usize)>,
+        chorded: Vec<(usize, usize)>,
+    ) {
+        // The last cell that was acted on, which is the one that gets announced
+        let acted_on = flagged
+            .iter()
+            .chain(&cleared)
+            .chain(&chorded)
+            .last()
+            .copied();
+        let revealed_before = self.field.revealed_count();
+        let lives_before = self.field.lives_left();
+        if acted_on.is_some() {
+            self.history.push(Snapshot {
+                field: self.field.clone(),
+                game_over: self.game_over,
+                stats: self.stats,
+                game_recorded: self.game_recorded,
+            });
+            if self.history.len() > UNDO_LIMIT {
+                self.history.remove(0);
+            }
+        }
+
+        for pos in flagged {
+            self.field.cycle_mark(pos, self.question_marks);
+        }
+        for pos in cleared {
+            // Try clearing the cell, if that is invalid, try clearing its neighbors
+            let exploded = match self.field.clear_cell(pos) {
+                None if self.chord_triggers.number_click => self.chord(pos),
+                result => result,
+            };
+            if exploded.unwrap_or_default() {
+                self.game_over = self.field.lives_left() == 0;
+            }
+        }
+        for pos in chorded {
+            if self.chord(pos).unwrap_or_default() {
+                self.game_over = self.field.lives_left() == 0;
+            }
+        }
+
+        if let Some(pos) = acted_on {
+            self.used_flags |= self.field.board.iter().any(|cell| cell.flags > 0);
+            self.record_result();
+            if self.flag_mines_on_win && self.field.complete() {
+                self.field.flag_mines();
+            }
+            self.rate_difficulty();
+            self.refresh_predictions();
+            self.announce_move(pos, revealed_before, lives_before);
+        }
+    }
+
+    /// Clears the neighbors of a revealed number, or if flag chording is on and they must all be
+    /// mines, flags them. Returns whether a mine exploded, or None if nothing could be done.
+    fn chord(&mut self, pos: (usize, usize)) -> Option<bool> {
+        if self.no_flag {
+            let inferred = self
+                .solver
+                .solver()
+                .predict(&self.field)
+                .mapv(|prob| prob == Some(1.0));
+            return self.field.clear_neighbors_inferred(pos, Some(&inferred));
+        }
+        self.field
+            .clear_neighbors(pos)
+            .or_else(|| (self.flag_chord && self.field.flag_neighbors(pos)).then_some(false))
+    }
+
+    fn describe_cell(&self, pos: (usize, usize)) -> String {
+        describe_cell(
+            self.field.board[pos],
+            pos,
+            self.predictions
+                .as_ref()
+                .and_then(|predictions| predictions[pos]),
+            self.game_over,
+        )
+    }
+
+    /// Updates the status (which is read out by screen readers) after the board was changed, with
+    /// `pos` being the cell that was clicked, flagged or chorded.
+    fn announce_move(&mut self, pos: (usize, usize), revealed_before: usize, lives_before: u8) {
+        let revealed = self.field.revealed_count().saturating_sub(revealed_before);
+        let lives_lost = lives_before.saturating_sub(self.field.lives_left());
+        self.status = if self.game_over {
+            "Game over, a mine exploded".to_owned()
+        } else if lives_lost > 0 {
+            format!("A mine exploded, {} lives left", self.field.lives_left())
+        } else if self.field.complete() {
+            "You win, all safe cells are revealed".to_owned()
+        } else if revealed > 1 {
+            format!("Revealed {revealed} cells")
+        } else {
+            self.describe_cell(pos)
+        };
+    }
+
+    /// Recomputes the predictions if they are being shown, after the field or solver changed.
+    fn refresh_predictions(&mut self) {
+        self.solver_comparison = None;
+        if let Some(predictions) = self.predictions.as_mut() {
+            let (preds, recommended, t) = self.field.get_predictions(self.solver.solver());
+            *predictions = preds;
+            self.recommended_guess = recommended;
+            self.solver_times[self.solver as usize] = Some(t);
+        }
+    }
+
+    /// Runs every solver on the field, and summarizes how many cells each one is certain about and
+    /// how many cells they disagree on. Each solver's time is recorded as well.
+    fn compare_solvers(&mut self) {
+        let certain = |prob: &Option<f32>| prob.filter(|prob| *prob == 0.0 || *prob == 1.0);
+        let (supported, unsupported): (Vec<_>, Vec<_>) = SolverChoice::ALL
+            .into_iter()
+            .partition(|choice| choice.supports(self.field.rules()));
+        let results = supported
+            .into_iter()
+            .map(|choice| {
+                let t0 = Instant::now();
+                let predictions = choice.solver().predict(&self.field);
+                self.solver_times[choice as usize] = Some(t0.elapsed());
+                (choice, predictions.mapv_into_any(|prob| certain(&prob)))
+            })
+            .collect::<Vec<_>>();
+
+        let mut lines = results
+            .iter()
+            .map(|(choice, predictions)| {
+                let count = predictions.iter().filter(|prob| prob.is_some()).count();
+                format!("{}: {count} cells certain", choice.name())
+            })
+            .chain(
+                unsupported
+                    .into_iter()
+                    .map(|choice| format!("{}: not supported on this board", choice.name())),
+            )
+            .collect::<Vec<_>>();
+        let differences = self
+            .field
+            .board
+            .indexed_iter()
+            .filter(|(pos, _)| {
+                results
+                    .iter()
+                    .map(|(_, predictions)| predictions[*pos])
+                    .tuple_windows()
+                    .any(|(a, b)| a != b)
+            })
+            .count();
+        lines.push(format!("{differences} cells differ"));
+        self.solver_comparison = Some(lines.join("\n"));
+    }
 }
 
 impl eframe::App for Minesweeper {
@@ -87,7 +1096,61 @@ impl eframe::App for Minesweeper {
                     ui.add_space(16.0);
                 }
 
+                ui.menu_button("Settings", |ui| {
+                    if ui.button("Key Bindings").clicked() {
+                        self.show_key_bindings = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Theme").clicked() {
+                        self.show_theme = true;
+                        ui.close_menu();
+                    }
+                    if !is_web {
+                        ui.checkbox(&mut self.fit_window, "Fit window to new boards");
+                        if ui.button("Toggle Fullscreen").clicked() {
+                            self.toggle_fullscreen(ctx);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                ui.add_space(16.0);
+
+                ui.menu_button("Puzzles", |ui| {
+                    if ui.button("Editor").clicked() {
+                        self.show_editor = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    for goal in Goal::ALL {
+                        ui.radio_value(&mut self.puzzle_goal, goal, goal.name());
+                    }
+                    let mut play = None;
+                    if ui.button("Random Training Puzzle").clicked() {
+                        play = Puzzle::generate(self.new_field_size, self.new_field_mines);
+                        if play.is_none() {
+                            self.status =
+                                "Couldn't generate a puzzle with these settings".to_owned();
+                        }
+                        ui.close_menu();
+                    }
+                    for puzzle in &self.puzzles {
+                        if ui.button(&puzzle.name).clicked() {
+                            play = Some(puzzle.clone());
+                            ui.close_menu();
+                        }
+                    }
+                    if let Some(puzzle) = play {
+                        self.start_round(&puzzle);
+                    }
+                });
+                ui.add_space(16.0);
+
                 egui::widgets::global_dark_light_mode_switch(ui);
+                ui.add_space(16.0);
+
+                ui.label("Tap to:");
+                ui.selectable_value(&mut self.tap_mode, TapMode::Reveal, "Reveal");
+                ui.selectable_value(&mut self.tap_mode, TapMode::Flag, "Flag");
             });
         });
 
@@ -102,71 +1165,231 @@ impl eframe::App for Minesweeper {
                 ui.label("Mines:");
                 ui.add(DragValue::new(&mut self.new_field_mines));
             });
-            if ui.button("New Game").clicked() {
-                if let Some(field) = Field::new(self.new_field_size, self.new_field_mines) {
-                    self.field = field;
-                    self.game_over = false;
-                    if let Some(predictions) = self.predictions.as_mut() {
-                        let (preds, t) = self.field.get_predictions();
-                        *predictions = preds;
-                        self.last_predictions_time = Some(t);
-                    }
+            ui.horizontal(|ui| {
+                ui.label("Mines per cell:");
+                ui.add(
+                    DragValue::new(&mut self.new_field_rules.mines_per_cell)
+                        .clamp_range(1..=MAX_MINES_PER_CELL),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Lives:");
+                ui.add(DragValue::new(&mut self.new_field_rules.lives).clamp_range(1..=u8::MAX));
+            });
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Mine density")
+                    .selected_text(self.new_field_rules.density.name())
+                    .show_ui(ui, |ui| {
+                        for density in Density::ALL {
+                            ui.selectable_value(
+                                &mut self.new_field_rules.density,
+                                density,
+                                density.name(),
+                            );
+                        }
+                    });
+                if self.new_field_rules.density == Density::Painted && ui.button("Paint").clicked()
+                {
+                    self.show_density_mask = true;
                 }
+            });
+            ui.checkbox(&mut self.new_field_rules.liar, "Liar mode")
+                .on_hover_text("Every number is off by one, either up or down");
+            if ui.button("New Game").clicked() {
+                self.new_game();
             }
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!self.history.is_empty(), egui::Button::new("Undo"))
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui.button("Hint").clicked() {
+                    self.hint();
+                }
+            });
+            self.puzzle_round_ui(ui);
+            let mut show_predictions = self.predictions.is_some();
             if ui
-                .checkbox(&mut self.predictions.is_some(), "Show Predictions")
+                .checkbox(&mut show_predictions, "Show Predictions")
                 .clicked()
             {
-                if self.predictions.is_none() {
-                    let (preds, t) =  self.field.get_predictions();
-                    self.predictions = Some(preds);
-                    self.last_predictions_time = Some(t);
-                } else {
-                    self.predictions = None;
-                }
+                self.set_show_predictions(show_predictions);
             };
-            if let Some(t) = self.last_predictions_time {
-                ui.label(format!("Last predictions time: {t:?}"));
+            egui::ComboBox::from_label("Prediction style")
+                .selected_text(self.overlay_style.name())
+                .show_ui(ui, |ui| {
+                    for style in OverlayStyle::ALL {
+                        ui.selectable_value(&mut self.overlay_style, style, style.name());
+                    }
+                });
+            let solver = self.solver;
+            egui::ComboBox::from_label("Solver")
+                .selected_text(self.solver.name())
+                .show_ui(ui, |ui| {
+                    for choice in SolverChoice::ALL {
+                        ui.add_enabled_ui(choice.supports(self.field.rules()), |ui| {
+                            ui.selectable_value(&mut self.solver, choice, choice.name())
+                                .on_disabled_hover_text(
+                                    "Can't solve boards with several mines per cell",
+                                );
+                        });
+                    }
+                });
+            if !self.solver.supports(self.field.rules()) {
+                ui.label(format!(
+                    "{} can't solve boards with several mines per cell",
+                    self.solver.name()
+                ));
+            }
+            if self.solver != solver {
+                self.refresh_predictions();
+            }
+            if ui.button("Compare Solvers").clicked() {
+                self.compare_solvers();
+            }
+            if let Some(comparison) = &self.solver_comparison {
+                ui.label(comparison);
+            }
+            for choice in SolverChoice::ALL {
+                if let Some(t) = self.solver_times[choice as usize] {
+                    ui.label(format!("Last {} predictions time: {t:?}", choice.name()));
+                }
+            }
+            if self.no_flag {
+                ui.label(format!("Mines: {}", self.field.total_mines()));
+            } else {
+                ui.label(format!("Remaining mines: {}", self.field.remaining_mines()));
+            }
+            if self.field.rules().lives > 1 {
+                ui.label(format!("Lives left: {}", self.field.lives_left()));
+            }
+            let status = ui.label(&self.status);
+            // Have screen readers announce the status whenever it changes
+            ctx.accesskit_node_builder(status.id, |builder| {
+                builder.set_live(egui::accesskit::Live::Polite);
+            });
+            ui.checkbox(&mut self.long_press_to_flag, "Long press to flag");
+            ui.label("Chord by:");
+            ui.indent("chord_triggers", |ui| {
+                let triggers = &mut self.chord_triggers;
+                ui.checkbox(&mut triggers.number_click, "Clicking a number");
+                ui.checkbox(&mut triggers.both_buttons, "Clicking both buttons");
+                ui.checkbox(&mut triggers.middle_click, "Middle clicking");
+            });
+            ui.checkbox(&mut self.no_flag, "No flags").on_hover_text(
+                "Flagging is turned off, and chording counts the mines that can be inferred",
+            );
+            ui.label(format!(
+                "Wins: {} ({} without flags), losses: {}",
+                self.stats.wins, self.stats.no_flag_wins, self.stats.losses
+            ));
+            ui.checkbox(&mut self.flag_chord, "Flag chord")
+                .on_hover_text(
+                    "Chording a number flags its hidden neighbors, if they must all be mines",
+                );
+            ui.checkbox(&mut self.flag_mines_on_win, "Flag all mines on win");
+            ui.checkbox(&mut self.question_marks, "Question marks");
+            ui.add(
+                Slider::new(&mut self.zoom, 1.0..=MAX_ZOOM)
+                    .logarithmic(true)
+                    .text("Zoom"),
+            );
+
+            ui.separator();
+            ui.checkbox(&mut self.limit_difficulty, "Limit difficulty");
+            ui.add_enabled_ui(self.limit_difficulty, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Score from");
+                    ui.add(DragValue::new(&mut self.difficulty_limits.0).speed(0.1));
+                    ui.label("to");
+                    ui.add(DragValue::new(&mut self.difficulty_limits.1).speed(0.1));
+                });
+            });
+            ui.checkbox(
+                &mut self.show_difficulty_during_game,
+                "Show difficulty during game",
+            );
+            let game_ended = self.game_over || self.field.complete();
+            match self.difficulty {
+                Some(difficulty) if game_ended || self.show_difficulty_during_game => {
+                    ui.label(format!("Difficulty: {:.1}", difficulty.score()));
+                    ui.label(format!("Guesses needed: {}", difficulty.guesses));
+                    if let Some(deepest) = difficulty.deepest() {
+                        ui.label(format!("Hardest deduction: {}", deepest.name()));
+                    }
+                    ui.label(format!("3BV: {}", difficulty.bbbv));
+                }
+                Some(_) => {
+                    ui.label("Difficulty: hidden until the game ends");
+                }
+                None if self.generating() => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Generating a board within the difficulty limits");
+                    });
+                }
+                None if self.rating.is_some() => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Rating difficulty");
+                    });
+                }
+                None => {
+                    ui.label("Difficulty: rated after the first click");
+                }
             }
-            ui.label(format!("Remaining mines: {}", self.field.remaining_mines()));
         });
 
         let mut flagged = Vec::new();
         let mut cleared = Vec::new();
+        let mut chorded = Vec::new();
         let game_complete = self.field.complete();
+        let scroll_to_selected;
 
         {
-            let ([up, down, left, right], [space, flag, esc, restart]) = ctx.input(|inp| {
-                (
-                    [
-                        [Key::ArrowUp, Key::W, Key::K],
-                        [Key::ArrowDown, Key::S, Key::J],
-                        [Key::ArrowLeft, Key::A, Key::H],
-                        [Key::ArrowRight, Key::D, Key::L],
-                    ]
-                    .map(|keys| keys.into_iter().any(|key| inp.key_pressed(key))),
-                    [Key::Space, Key::F, Key::Escape, Key::R].map(|key| inp.key_pressed(key)),
-                )
+            let previously_selected = self.selected;
+            // Keys typed into a text field (like a puzzle's name) aren't actions
+            let ignore_keys = self.capturing_key.is_some() || ctx.wants_keyboard_input();
+            let pressed = ctx.input(|inp| {
+                Action::ALL.map(|action| !ignore_keys && self.key_bindings.pressed(inp, action))
             });
+            // Action::ALL is in declaration order, so actions can be used as indices into it
+            let pressed = |action: Action| pressed[action as usize];
+            let [up, down, left, right] = [
+                Action::MoveUp,
+                Action::MoveDown,
+                Action::MoveLeft,
+                Action::MoveRight,
+            ]
+            .map(pressed);
+            let (reveal, flag, chord) = (
+                pressed(Action::Reveal),
+                pressed(Action::Flag),
+                pressed(Action::Chord),
+            );
 
-            if restart {
-                if let Some(field) = Field::new(self.new_field_size, self.new_field_mines) {
-                    self.field = field;
-                    self.game_over = false;
-                    if let Some(predictions) = self.predictions.as_mut() {
-                        let (preds, t) = self.field.get_predictions();
-                        *predictions = preds;
-                        self.last_predictions_time = Some(t);
-                    }
-                }
+            if pressed(Action::NewGame) {
+                self.new_game();
+            } else if pressed(Action::Undo) {
+                self.undo();
+            } else if pressed(Action::TogglePredictions) {
+                self.set_show_predictions(self.predictions.is_none());
+            } else if pressed(Action::Hint) {
+                self.hint();
+            } else if pressed(Action::ToggleFullscreen) {
+                self.toggle_fullscreen(ctx);
             } else if let Some((x, y)) = self.selected.as_mut() {
                 let (width, height) = self.field.size();
-                if esc {
+                if pressed(Action::Deselect) {
                     self.selected = None;
-                } else if space {
+                } else if reveal {
                     cleared.push((*x, *y));
                 } else if flag {
                     flagged.push((*x, *y));
+                } else if chord {
+                    chorded.push((*x, *y));
                 } else {
                     if up && *y > 0 {
                         *y -= 1;
@@ -181,121 +1404,339 @@ impl eframe::App for Minesweeper {
                         *x += 1;
                     }
                 }
-            } else if up || down || left || right || space || flag {
+            } else if up || down || left || right || reveal || flag || chord {
                 self.selected = Some((0, 0));
             }
+            scroll_to_selected = up || down || left || right || pressed(Action::Hint);
+
+            if let Some(pos) = self
+                .selected
+                .filter(|_| self.selected != previously_selected)
+            {
+                self.status = self.describe_cell(pos);
+            }
         }
 
+        self.key_bindings_window(ctx);
+        self.theme_window(ctx);
+        self.density_mask_window(ctx);
+        self.editor_window(ctx);
+
+        if ctx.input(|inp| inp.any_touches()) {
+            self.touch_detected = true;
+        }
+        ctx.input(|inp| {
+            if inp.pointer.any_pressed() {
+                self.touch_press = inp.events.iter().any(|event| {
+                    matches!(
+                        event,
+                        egui::Event::Touch {
+                            phase: egui::TouchPhase::Start,
+                            ..
+                        }
+                    )
+                });
+            }
+        });
+
         egui::CentralPanel::default().show(ctx, |ui| {
-            let grid_size = ui.available_size();
+            let available_size = ui.available_size();
+            if self.pending_fit_window {
+                self.pending_fit_window = false;
+                self.resize_to_fit(ctx, available_size);
+            }
             let field_size = self.field.size();
-            let cell_size = f32::min(
-                grid_size.x / field_size.0 as f32,
-                grid_size.y / field_size.1 as f32,
+            let fit_cell_size = f32::min(
+                available_size.x / field_size.0 as f32,
+                available_size.y / field_size.1 as f32,
             );
+            // Fingers need bigger targets than a mouse pointer
+            let min_cell_size = if self.touch_detected {
+                MIN_TOUCH_CELL_SIZE
+            } else {
+                MIN_CELL_SIZE
+            };
+            let previous_cell_size = (fit_cell_size * self.zoom).max(min_cell_size);
+
+            let zoom_delta = ui.input(|inp| inp.zoom_delta());
+            if zoom_delta != 1.0 && ui.ui_contains_pointer() {
+                self.zoom = (self.zoom * zoom_delta).clamp(1.0, MAX_ZOOM);
+            }
+            let cell_size = (fit_cell_size * self.zoom).max(min_cell_size);
             let final_grid_size = vec2(field_size.0 as f32, field_size.1 as f32) * cell_size;
 
-            ui.scope(|ui| {
+            // Keep the point under the cursor (or the middle of the view) in place while zooming
+            if cell_size != previous_cell_size {
+                if let Some((view_rect, offset)) = self.last_field_view {
+                    let anchor = ui
+                        .input(|inp| inp.pointer.hover_pos())
+                        .filter(|pos| view_rect.contains(*pos))
+                        .unwrap_or(view_rect.center())
+                        - view_rect.min;
+                    self.pending_scroll_offset =
+                        Some((anchor + offset) * (cell_size / previous_cell_size) - anchor);
+                }
+            }
+
+            let mut scroll_area = egui::ScrollArea::both().auto_shrink(false);
+            if let Some(offset) = self.pending_scroll_offset.take() {
+                scroll_area = scroll_area.scroll_offset(offset);
+            }
+            let cell_theme = self.theme(ui.visuals());
+            let output = scroll_area.show_viewport(ui, |ui, viewport| {
                 ui.spacing_mut().interact_size = Vec2::ZERO;
-                let response = egui::Grid::new("field").spacing((0.0, 0.0)).show(ui, |ui| {
-                    for (y, row) in self
-                        .field
-                        .board
-                        .lanes(ndarray::Axis(0))
-                        .into_iter()
-                        .enumerate()
-                    {
-                        for (x, cell) in row.indexed_iter() {
-                            let response = ui.add(
-                                cell.show(
+                ui.set_min_size(final_grid_size);
+                let origin = ui.max_rect().min;
+
+                // Only lay out the cells that can actually be seen
+                let visible_cells = |min: f32, max: f32, len: usize| {
+                    let first = (min / cell_size).floor().max(0.0) as usize;
+                    let last = ((max / cell_size).ceil().max(0.0) as usize).min(len);
+                    first..last
+                };
+                let xs = visible_cells(viewport.min.x, viewport.max.x, field_size.0);
+                let ys = visible_cells(viewport.min.y, viewport.max.y, field_size.1);
+
+                for y in ys {
+                    for x in xs.clone() {
+                        let cell_rect = Rect::from_min_size(
+                            origin + vec2(x as f32, y as f32) * cell_size,
+                            Vec2::splat(cell_size),
+                        );
+                        let (prediction, recommended) = self.cell_overlay((x, y));
+                        let response = ui.put(
+                            cell_rect,
+                            self.field.board[(x, y)]
+                                .show(
                                     cell_size,
                                     self.selected == Some((x, y)),
-                                    self.predictions
-                                        .as_ref()
-                                        .and_then(|predictions| predictions[(x, y)]),
-                                ),
-                            );
-                            if !self.game_over && !game_complete {
-                                if response.clicked() {
-                                    if ctx.input(|inp| inp.modifiers.shift) {
-                                        flagged.push((x, y));
-                                    } else {
-                                        cleared.push((x, y));
-                                    }
+                                    prediction,
+                                    recommended,
+                                    cell_theme,
+                                    self.game_over,
+                                )
+                                .overlay_style(self.overlay_style)
+                                .position((x, y)),
+                        );
+                        if !self.game_over && !game_complete {
+                            let (both_down, pointer) = ctx.input(|inp| {
+                                (
+                                    inp.pointer.button_down(PointerButton::Primary)
+                                        && inp.pointer.button_down(PointerButton::Secondary),
+                                    inp.pointer.hover_pos(),
+                                )
+                            });
+                            if self.chord_triggers.both_buttons
+                                && both_down
+                                && pointer.is_some_and(|pos| cell_rect.contains(pos))
+                            {
+                                self.both_pressed = Some((x, y));
+                            }
+                            // Releasing both buttons shouldn't also count as a click of each
+                            if self.both_pressed.is_some() {
+                                continue;
+                            }
+
+                            if self.long_press_to_flag
+                                && self.touch_press
+                                && self.long_pressed != Some((x, y))
+                                && response.is_pointer_button_down_on()
+                            {
+                                let (held_for, dragging) = ctx.input(|inp| {
+                                    (
+                                        inp.pointer
+                                            .press_start_time()
+                                            .map_or(0.0, |start| inp.time - start),
+                                        inp.pointer.is_decidedly_dragging(),
+                                    )
+                                });
+                                if held_for >= LONG_PRESS_DURATION && !dragging {
+                                    flagged.push((x, y));
+                                    self.long_pressed = Some((x, y));
+                                } else {
+                                    // Keep checking while the press is held, even without input
+                                    ctx.request_repaint();
                                 }
-                                if response.secondary_clicked() {
+                            }
+                            if response.clicked() && self.long_pressed != Some((x, y)) {
+                                let flag_tap = ctx.input(|inp| inp.modifiers.shift)
+                                    != (self.tap_mode == TapMode::Flag);
+                                // Tapping a number always chords, even in flag mode
+                                let cell_hidden = matches!(
+                                    self.field.board[(x, y)].state,
+                                    CellState::Unrevealed
+                                        | CellState::Flagged
+                                        | CellState::Questioned
+                                );
+                                if flag_tap && cell_hidden {
                                     flagged.push((x, y));
+                                } else {
+                                    cleared.push((x, y));
                                 }
                             }
+                            if response.secondary_clicked() {
+                                flagged.push((x, y));
+                            }
+                            if self.chord_triggers.middle_click && response.middle_clicked() {
+                                chorded.push((x, y));
+                            }
                         }
-                        ui.end_row();
                     }
-                });
-                if self.game_over {
-                    ui.painter().text(
-                        response.response.rect.center(),
-                        Align2::CENTER_CENTER,
-                        "GAME\nOVER",
-                        FontId::proportional(final_grid_size.x.min(final_grid_size.y) / 4.0),
-                        Color32::RED,
-                    );
                 }
-                if game_complete {
-                    ui.painter().text(
-                        response.response.rect.center(),
-                        Align2::CENTER_CENTER,
-                        "YOU\nWIN",
-                        FontId::proportional(final_grid_size.x.min(final_grid_size.y) / 4.0),
-                        Color32::GREEN,
+
+                if let Some((x, y)) = self.selected.filter(|_| scroll_to_selected) {
+                    ui.scroll_to_rect(
+                        Rect::from_min_size(
+                            origin + vec2(x as f32, y as f32) * cell_size,
+                            Vec2::splat(cell_size),
+                        ),
+                        None,
                     );
                 }
             });
-        });
+            self.last_field_view = Some((output.inner_rect, output.state.offset));
+            if !ctx.input(|inp| inp.pointer.any_down()) {
+                self.long_pressed = None;
+                if let Some(pos) = self.both_pressed.take() {
+                    chorded.push(pos);
+                }
+            }
 
-        let board_changed = !flagged.is_empty() || !cleared.is_empty();
+            let visible_field = output
+                .inner_rect
+                .intersect(Rect::from_min_size(output.inner_rect.min, final_grid_size));
+            let font_size = visible_field.width().min(visible_field.height()) / 4.0;
+            if self.game_over {
+                ui.painter().text(
+                    visible_field.center(),
+                    Align2::CENTER_CENTER,
+                    "GAME\nOVER",
+                    FontId::proportional(font_size),
+                    Color32::RED,
+                );
+            }
+            if game_complete {
+                ui.painter().text(
+                    visible_field.center(),
+                    Align2::CENTER_CENTER,
+                    "YOU\nWIN",
+                    FontId::proportional(font_size),
+                    Color32::GREEN,
+                );
+            }
 
-        for pos in flagged {
-            self.field.toggle_flag(pos);
-        }
-        for pos in cleared {
-            // Try clearing the cell, if that is invalid, try clearing its neighbors
-            if self
-                .field
-                .clear_cell(pos)
-                .or_else(|| self.field.clear_neighbors(pos))
-                .unwrap_or_default()
-            {
-                self.game_over = true;
+            let zoomed = final_grid_size.x > output.inner_rect.width() + 0.5
+                || final_grid_size.y > output.inner_rect.height() + 0.5;
+            if zoomed {
+                if let Some(offset) =
+                    self.show_minimap(ui, output.inner_rect, output.state.offset, cell_size)
+                {
+                    self.pending_scroll_offset = Some(offset);
+                }
             }
+        });
+
+        if self.no_flag {
+            flagged.clear();
+        }
+        // The board can't be played while it might still be replaced by a different one
+        if self.generating() {
+            flagged.clear();
+            cleared.clear();
+            chorded.clear();
         }
 
-        if board_changed {
-            if let Some(predictions) = self.predictions.as_mut() {
-                let (preds, t) = self.field.get_predictions();
-                *predictions = preds;
-                self.last_predictions_time = Some(t);
+        // In puzzle mode, cells are picked instead of being revealed
+        if let Some(round) = self.puzzle_round.as_mut() {
+            for pos in cleared.drain(..).chain(chorded.drain(..)) {
+                round.toggle_pick(&self.field, pos);
+                self.status = format!("{} cells picked", round.picks.len());
             }
         }
+
+        self.make_moves(flagged, cleared, chorded);
+        self.continue_rating(ctx);
     }
 }
 
 impl Field {
-    fn get_predictions(&self) -> (Array2<Option<Prediction>>, Duration) {
+    fn get_predictions(
+        &self,
+        solver: &dyn Solver,
+    ) -> (Array2<Option<Prediction>>, Option<(usize, usize)>, Duration) {
         let t0 = Instant::now();
+        let probabilities = solver.predict(self);
+        let recommended = recommend_guess(self, &probabilities);
         let predictions =
-            predict(self).mapv_into_any(|pred| pred.map(Prediction::from_probability));
+            probabilities.mapv_into_any(|pred| pred.map(Prediction::from_probability));
         let t1 = Instant::now();
-        (predictions, t1 - t0)
+        (predictions, recommended, t1 - t0)
     }
 }
 
+/// Draws a row of example cells in the given theme.
+fn theme_preview(ui: &mut egui::Ui, theme: Theme) {
+    const SIZE: f32 = 24.0;
+
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing = Vec2::ZERO;
+        let examples = [
+            CellState::Unrevealed,
+            CellState::Flagged,
+            CellState::Questioned,
+            CellState::Empty,
+        ]
+        .map(|state| (state, 0))
+        .into_iter()
+        .chain((1..=8).map(|n| (CellState::Revealed, n)))
+        .chain([(CellState::Exploded, 0)]);
+        for (state, neighbors) in examples {
+            let cell = Cell {
+                state,
+                neighbors,
+                mines: (state == CellState::Exploded) as u8,
+                flags: (state == CellState::Flagged) as u8,
+                offset: 0,
+            };
+            ui.add(cell.show(SIZE, false, None, false, theme, false));
+        }
+
+        let (rect, _) = ui.allocate_exact_size(Vec2::splat(SIZE), Sense::hover());
+        ui.painter()
+            .rect_filled(rect, Rounding::ZERO, theme.revealed_fill);
+        theme::paint_mine(ui.painter(), rect, theme.mine);
+        let (rect, _) = ui.allocate_exact_size(Vec2::splat(SIZE), Sense::hover());
+        ui.painter()
+            .rect_filled(rect, Rounding::ZERO, theme.hidden_fill);
+        theme::paint_wrong_flag(
+            ui.painter(),
+            rect,
+            Stroke::new(SIZE / 16.0, theme.text),
+            theme.flag,
+        );
+    });
+}
+
 impl Cell {
-    fn show(self, size: f32, selected: bool, prediction: Option<Prediction>) -> CellWidget {
+    fn show(
+        self,
+        size: f32,
+        selected: bool,
+        prediction: Option<Prediction>,
+        recommended: bool,
+        theme: Theme,
+        game_over: bool,
+    ) -> CellWidget {
         CellWidget {
             cell: self,
             size,
             selected,
             prediction,
+            overlay_style: OverlayStyle::Colors,
+            position: None,
+            recommended,
+            theme,
+            game_over,
         }
     }
 }
@@ -305,99 +1746,274 @@ struct CellWidget {
     size: f32,
     selected: bool,
     prediction: Option<Prediction>,
+    overlay_style: OverlayStyle,
+    position: Option<(usize, usize)>,
+    recommended: bool,
+    theme: Theme,
+    /// Whether to show where the mines were
+    game_over: bool,
+}
+
+impl CellWidget {
+    fn overlay_style(mut self, overlay_style: OverlayStyle) -> Self {
+        self.overlay_style = overlay_style;
+        self
+    }
+
+    /// Sets the cell's position on the board, which lets screen readers describe it.
+    fn position(mut self, pos: (usize, usize)) -> Self {
+        self.position = Some(pos);
+        self
+    }
+}
+
+/// Describes a cell in words, for screen readers.
+fn describe_cell(
+    cell: Cell,
+    (x, y): (usize, usize),
+    prediction: Option<Prediction>,
+    game_over: bool,
+) -> String {
+    let hidden = if cell.state == CellState::Questioned {
+        "question mark"
+    } else {
+        "unrevealed"
+    };
+    let state = match cell.state {
+        state if state.is_unrevealed() && game_over && cell.mines > 1 => {
+            format!("{} mines", cell.mines)
+        }
+        state if state.is_unrevealed() && game_over && cell.has_mine() => "mine".to_owned(),
+        CellState::Unrevealed | CellState::Questioned => match prediction {
+            Some(Prediction::Free) => format!("{hidden}, safe"),
+            Some(Prediction::Mine) => format!("{hidden}, mine"),
+            Some(Prediction::Probability(prob)) => {
+                format!(
+                    "{hidden}, {:.0}% chance of a mine",
+                    (prob * 100.0).round().clamp(1.0, 99.0)
+                )
+            }
+            None => hidden.to_owned(),
+        },
+        CellState::Flagged if game_over && cell.flags != cell.mines => "wrongly flagged".to_owned(),
+        CellState::Flagged if cell.flags > 1 => format!("{} flags", cell.flags),
+        CellState::Flagged => "flagged".to_owned(),
+        CellState::Revealed => cell.number().to_string(),
+        CellState::Exploded => "exploded mine".to_owned(),
+        CellState::Empty => "empty".to_owned(),
+    };
+    format!("Row {}, column {}: {state}", y + 1, x + 1)
 }
 
 impl Widget for CellWidget {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
         let (rect, response) = ui.allocate_exact_size(Vec2::splat(self.size), Sense::click());
+        if let Some(pos) = self.position {
+            response.widget_info(|| {
+                WidgetInfo::labeled(
+                    WidgetType::Button,
+                    describe_cell(self.cell, pos, self.prediction, self.game_over),
+                )
+            });
+        }
 
-        let stroke = Stroke::from((rect.width() / 16.0, ui.style().visuals.strong_text_color()));
+        let theme = self.theme;
+        let stroke = Stroke::from((rect.width() / 16.0, theme.text));
+        let hidden = match self.cell.state {
+            CellState::Unrevealed | CellState::Questioned => {
+                !(self.game_over && self.cell.has_mine())
+            }
+            CellState::Flagged => true,
+            _ => false,
+        };
 
         let painter = ui.painter();
 
         painter.rect(
             rect.shrink(stroke.width / 2.0),
             Rounding::ZERO,
-            ui.style().visuals.window_fill(),
+            match self.cell.state {
+                _ if hidden => theme.hidden_fill,
+                CellState::Exploded => theme.exploded_fill,
+                _ => theme.revealed_fill,
+            },
             (
                 stroke.width,
                 if self.selected {
-                    Color32::BLUE
+                    theme.selected
                 } else {
-                    ui.style().visuals.window_stroke().color
+                    theme.border
                 },
             ),
         );
 
-        let draw_inner_border = || {
-            painter.rect_stroke(
-                rect.shrink(stroke.width * 1.5),
-                Rounding::ZERO,
-                (
-                    stroke.width,
-                    match (self.selected, response.hovered()) {
-                        (true, false) => Color32::BLUE,
-                        (_, true) => Color32::LIGHT_BLUE,
-                        _ => stroke.color,
-                    },
-                ),
-            );
-        };
+        if hidden {
+            if theme.tiles == TileStyle::Raised {
+                theme::paint_raised(painter, rect.shrink(stroke.width), stroke.width * 2.0);
+            }
+            let border_color = match (self.selected, response.hovered()) {
+                (true, false) => Some(theme.selected),
+                (_, true) => Some(theme.hovered),
+                _ if theme.tiles == TileStyle::Flat => Some(stroke.color),
+                _ => None,
+            };
+            if let Some(color) = border_color {
+                painter.rect_stroke(
+                    rect.shrink(stroke.width * 1.5),
+                    Rounding::ZERO,
+                    (stroke.width, color),
+                );
+            }
+        }
 
         match self.cell.state {
-            CellState::Unrevealed => {
-                draw_inner_border();
-
+            state if state.is_unrevealed() && self.game_over && self.cell.has_mine() => {
+                theme::paint_mine(painter, rect, theme.mine);
+                if self.cell.mines > 1 {
+                    paint_count(painter, rect, self.cell.mines, theme.text);
+                }
+            }
+            CellState::Unrevealed | CellState::Questioned => {
+                if self.cell.state == CellState::Questioned {
+                    painter.text(
+                        rect.center() + vec2(0.0, rect.height() / 20.0),
+                        Align2::CENTER_CENTER,
+                        "?",
+                        FontId::monospace(rect.height() * 0.8),
+                        theme.text,
+                    );
+                }
                 if let Some(prediction) = self.prediction {
-                    let color = match prediction {
-                        Prediction::Free => Color32::GREEN,
-                        Prediction::Mine => Color32::RED,
-                        Prediction::Contradiction => Color32::LIGHT_RED,
-                        Prediction::Probability(prob, _) => Color32::YELLOW.gamma_multiply(prob),
-                    };
-                    painter.rect_filled(rect.shrink(rect.height() / 5.0), Rounding::ZERO, color);
+                    theme::paint_prediction(
+                        painter,
+                        rect,
+                        prediction,
+                        self.overlay_style,
+                        theme.text,
+                    );
+                }
+
+                if self.recommended {
+                    painter.circle_stroke(
+                        rect.center(),
+                        rect.height() / 4.0,
+                        (stroke.width * 1.5, Color32::BLUE),
+                    );
                 }
             }
+            CellState::Flagged if self.game_over && self.cell.flags != self.cell.mines => {
+                theme::paint_wrong_flag(painter, rect, stroke, theme.flag);
+            }
             CellState::Flagged => {
-                draw_inner_border();
-                let flag_rect = rect.shrink2(rect.size() / vec2(3.0, 4.0));
-                painter.line_segment([flag_rect.left_top(), flag_rect.left_bottom()], stroke);
-                painter.rect_filled(
-                    flag_rect.with_max_y(flag_rect.center().y),
-                    Rounding::ZERO,
-                    stroke.color,
-                );
+                theme::paint_flag(painter, rect, stroke, theme.flag);
+                if self.cell.flags > 1 {
+                    paint_count(painter, rect, self.cell.flags, theme.text);
+                }
             }
             CellState::Revealed => {
                 painter.text(
                     rect.center() + vec2(0.0, rect.height() / 20.0),
                     Align2::CENTER_CENTER,
-                    self.cell.neighbors.to_string(),
+                    self.cell.number().to_string(),
                     FontId::monospace(rect.height() * 0.8),
-                    stroke.color,
+                    theme.number_color(self.cell.number()),
                 );
             }
-            CellState::Exploded => {
-                let draw_starburst = |radius, color| {
-                    let outer_rad = vec2(0.0, radius);
-                    let inner_rad = outer_rad * 0.75;
-
-                    let points = (0..=16)
-                        .map(|r| {
-                            let v = if r % 2 == 0 { inner_rad } else { outer_rad };
-                            let rot = Rot2::from_angle(r as f32 / -16.0 * std::f32::consts::TAU);
-                            rect.center() + (rot * v)
-                        })
-                        .collect();
-                    painter.add(Shape::convex_polygon(points, color, Stroke::NONE));
-                };
-
-                draw_starburst(rect.height() / 2.5, Color32::YELLOW);
-                draw_starburst(rect.height() / 4.0, Color32::LIGHT_RED);
-            }
+            CellState::Exploded => match theme.tiles {
+                TileStyle::Flat => theme::paint_starburst(painter, rect),
+                TileStyle::Raised => theme::paint_mine(painter, rect, theme.mine),
+            },
             CellState::Empty => {}
         }
 
         response
     }
 }
+
+/// Labels a cell holding several flags or mines with how many, in its corner.
+fn paint_count(painter: &egui::Painter, rect: Rect, count: u8, color: Color32) {
+    painter.text(
+        rect.right_bottom(),
+        Align2::RIGHT_BOTTOM,
+        count.to_string(),
+        FontId::monospace(rect.height() * 0.4),
+        color,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A game on a board with one mine in the corner, where clearing the opposite corner wins.
+    fn game() -> Minesweeper {
+        Minesweeper {
+            field: Field::from_mines((3, 3), &[(0, 0)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn no_flag_wins() {
+        let mut app = game();
+        app.make_moves(vec![], vec![(2, 2)], vec![]);
+        assert_eq!(
+            app.stats,
+            Stats {
+                wins: 1,
+                no_flag_wins: 1,
+                losses: 0
+            }
+        );
+
+        let mut app = game();
+        app.make_moves(vec![(0, 0)], vec![], vec![]);
+        app.make_moves(vec![], vec![(2, 2)], vec![]);
+        assert_eq!(
+            app.stats,
+            Stats {
+                wins: 1,
+                no_flag_wins: 0,
+                losses: 0
+            }
+        );
+
+        // Taking the flag off again still counts as having flagged
+        let mut app = game();
+        app.make_moves(vec![(1, 1)], vec![], vec![]);
+        app.make_moves(vec![(1, 1)], vec![], vec![]);
+        app.make_moves(vec![], vec![(2, 2)], vec![]);
+        assert_eq!(app.stats.wins, 1);
+        assert_eq!(app.stats.no_flag_wins, 0);
+    }
+
+    #[test]
+    fn undone_results() {
+        let mut app = game();
+        app.make_moves(vec![], vec![(0, 0)], vec![]);
+        assert!(app.game_over);
+        assert_eq!(app.stats.losses, 1);
+
+        // Losing again after undoing the loss still counts once
+        app.undo();
+        app.make_moves(vec![], vec![(0, 0)], vec![]);
+        assert_eq!(app.stats.losses, 1);
+
+        // Undoing the loss takes it back out, and the win is counted instead
+        app.undo();
+        assert!(!app.game_over);
+        assert_eq!(app.stats, Stats::default());
+        app.make_moves(vec![], vec![(2, 2)], vec![]);
+        let won = Stats {
+            wins: 1,
+            no_flag_wins: 1,
+            losses: 0,
+        };
+        assert_eq!(app.stats, won);
+
+        // Winning again after undoing the win still counts once
+        app.undo();
+        app.make_moves(vec![], vec![(2, 2)], vec![]);
+        assert_eq!(app.stats, won);
+    }
+}
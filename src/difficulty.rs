@@ -0,0 +1,200 @@
+use ndarray::Array2;
+
+use crate::{
+    game::{neighbors, Field},
+    solver::{predict, predict_local, predict_single, recommend_guess},
+};
+
+/// How much each forced guess adds to the difficulty score.
+const GUESS_WEIGHT: f32 = 10.0;
+/// How much each step that needed combining several numbers adds to the difficulty score.
+const PAIRWISE_WEIGHT: f32 = 1.0;
+/// How much each step that needed the total mine count adds to the difficulty score.
+const GLOBAL_WEIGHT: f32 = 3.0;
+/// How much each click of 3BV adds to the difficulty score.
+const BBBV_WEIGHT: f32 = 0.1;
+
+/// The kinds of deduction needed to make progress, from easiest to hardest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Deduction {
+    /// A single number is enough by itself.
+    Single,
+    /// Several numbers need to be combined.
+    Pairwise,
+    /// The total mine count is needed.
+    Global,
+}
+
+impl Deduction {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Single => "single number",
+            Self::Pairwise => "pairwise",
+            Self::Global => "global count",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Difficulty {
+    /// The number of times no cell could be deduced, and a guess was needed.
+    pub(crate) guesses: usize,
+    /// The number of solving steps that needed each kind of deduction, indexed by [`Deduction`].
+    pub(crate) steps: [usize; 3],
+    /// The minimum number of clicks needed to clear the board.
+    pub(crate) bbbv: usize,
+}
+
+impl Difficulty {
+    /// Rates a board by playing it out with the solver, from the cells that are revealed now.
+    /// Guesses are always assumed to be survived.
+    pub(crate) fn rate_position(field: Field) -> Self {
+        let mut rating = Rating::new(field);
+        loop {
+            if let Some(difficulty) = rating.step() {
+                return difficulty;
+            }
+        }
+    }
+
+    /// The hardest kind of deduction that was needed, if any were.
+    pub(crate) fn deepest(&self) -> Option<Deduction> {
+        [Deduction::Global, Deduction::Pairwise, Deduction::Single]
+            .into_iter()
+            .find(|deduction| self.steps[*deduction as usize] > 0)
+    }
+
+    /// A single number summarizing the difficulty, for comparing boards.
+    pub(crate) fn score(&self) -> f32 {
+        self.guesses as f32 * GUESS_WEIGHT
+            + self.steps[Deduction::Pairwise as usize] as f32 * PAIRWISE_WEIGHT
+            + self.steps[Deduction::Global as usize] as f32 * GLOBAL_WEIGHT
+            + self.bbbv as f32 * BBBV_WEIGHT
+    }
+}
+
+/// A rating that is played out a step at a time, so that it can be spread over several frames.
+#[derive(Debug, Clone)]
+pub(crate) struct Rating {
+    field: Field,
+    difficulty: Difficulty,
+}
+
+impl Rating {
+    /// Starts rating a board from its first click, like [`Difficulty::rate_position`]. Returns
+    /// None if the board has not been clicked yet.
+    pub(crate) fn start(field: &Field) -> Option<Self> {
+        field.first_click()?;
+        Some(Self::new(field.replay_first_click()))
+    }
+
+    fn new(field: Field) -> Self {
+        Self {
+            difficulty: Difficulty {
+                guesses: 0,
+                steps: [0; 3],
+                bbbv: bbbv(&field),
+            },
+            field,
+        }
+    }
+
+    /// Makes one deduction or guess. Returns the difficulty once the board is finished.
+    pub(crate) fn step(&mut self) -> Option<Difficulty> {
+        let field = &mut self.field;
+        if field.complete() {
+            return Some(self.difficulty);
+        }
+
+        let single = predict_single(field);
+        if apply(field, &single) {
+            self.difficulty.steps[Deduction::Single as usize] += 1;
+            return None;
+        }
+        let local = predict_local(field);
+        if apply(field, &local) {
+            self.difficulty.steps[Deduction::Pairwise as usize] += 1;
+            return None;
+        }
+        let probabilities = predict(field);
+        if apply(field, &probabilities) {
+            self.difficulty.steps[Deduction::Global as usize] += 1;
+            return None;
+        }
+
+        self.difficulty.guesses += 1;
+        let Some(pos) = recommend_guess(field, &probabilities).or_else(|| {
+            field
+                .board
+                .indexed_iter()
+                .find(|(_, cell)| cell.state.is_unrevealed())
+                .map(|(pos, _)| pos)
+        }) else {
+            return Some(self.difficulty);
+        };
+        let mines = field.board[pos].mines;
+        if mines > 0 {
+            field.set_flags(pos, mines);
+        } else {
+            field.clear_cell(pos);
+        }
+        None
+    }
+}
+
+/// Reveals every cell predicted to be free, and flags every cell predicted to be a mine. Returns
+/// whether anything was changed.
+fn apply(field: &mut Field, predictions: &Array2<Option<f32>>) -> bool {
+    let mut changed = false;
+    for (pos, prediction) in predictions.indexed_iter() {
+        if !field.board[pos].state.is_unrevealed() {
+            continue;
+        }
+        match prediction {
+            Some(prob) if *prob == 0.0 => {
+                field.clear_cell(pos);
+                changed = true;
+            }
+            Some(prob) if *prob == 1.0 => {
+                field.set_flags(pos, field.rules().mines_per_cell);
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+    changed
+}
+
+/// The Bechtel's Board Benchmark Value: the number of openings, plus the number of numbered cells
+/// that are not next to an opening.
+fn bbbv(field: &Field) -> usize {
+    let mut seen = Array2::<bool>::default(field.size());
+    let mut clicks = 0;
+
+    for (pos, cell) in field.board.indexed_iter() {
+        if cell.has_mine() || cell.neighbors != 0 || seen[pos] {
+            continue;
+        }
+
+        clicks += 1;
+        seen[pos] = true;
+        let mut check = vec![pos];
+        while let Some(next_pos) = check.pop() {
+            for neighbor_pos in neighbors(&field.board, next_pos) {
+                if !seen[neighbor_pos] {
+                    seen[neighbor_pos] = true;
+                    if field.board[neighbor_pos].neighbors == 0 {
+                        check.push(neighbor_pos);
+                    }
+                }
+            }
+        }
+    }
+
+    clicks
+        + field
+            .board
+            .indexed_iter()
+            .filter(|(pos, cell)| !cell.has_mine() && !seen[*pos])
+            .count()
+}
@@ -1,12 +1,91 @@
+use std::iter;
+
 use ndarray::Array2;
-use rand::{distributions::Uniform, rngs::SmallRng, Rng, SeedableRng};
+use rand::{
+    distributions::{Distribution, Uniform, WeightedIndex},
+    rngs::SmallRng,
+    Rng, SeedableRng,
+};
+
+/// The most mines a single cell can be allowed to hold.
+pub(crate) const MAX_MINES_PER_CELL: u8 = 8;
+
+/// Variations on the classic rules that a field can be generated with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Rules {
+    /// The most mines a single cell can hold. Numbers count every mine, and flags can be stacked
+    /// on a cell up to this many.
+    pub(crate) mines_per_cell: u8,
+    /// Whether every number is off by exactly one, up or down. Empty cells are still empty, so that
+    /// they can be opened up.
+    pub(crate) liar: bool,
+    /// How many mines can explode before the game is over. Exploded mines stay marked, and the
+    /// game carries on.
+    pub(crate) lives: u8,
+    /// Where on the board mines are more likely to be placed.
+    pub(crate) density: Density,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            mines_per_cell: 1,
+            liar: false,
+            lives: 1,
+            density: Density::Uniform,
+        }
+    }
+}
 
-#[derive(Debug)]
+/// How likely each cell is to be picked when placing mines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum Density {
+    /// Every cell is equally likely, as in the classic game
+    Uniform,
+    /// Mines are bunched up around a few random points
+    Clustered,
+    /// Mines get denser from the left side of the board to the right
+    Gradient,
+    /// Each cell is weighted by a mask painted by the player
+    Painted,
+}
+
+impl Density {
+    pub(crate) const ALL: [Self; 4] = [
+        Self::Uniform,
+        Self::Clustered,
+        Self::Gradient,
+        Self::Painted,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Uniform => "Uniform",
+            Self::Clustered => "Clustered",
+            Self::Gradient => "Gradient",
+            Self::Painted => "Painted",
+        }
+    }
+}
+
+/// Roughly how many cells there are for each cluster of mines.
+const CELLS_PER_CLUSTER: usize = 60;
+/// How far a cluster spreads, in cells.
+const CLUSTER_SPREAD: f32 = 2.0;
+/// How likely a cell far away from every cluster is to get a mine, relative to a cluster's center.
+const CLUSTER_BACKGROUND: f32 = 0.05;
+
+#[derive(Debug, Clone)]
 pub(crate) struct Field {
     pub(crate) board: Array2<Cell>,
     mines: usize,
+    rules: Rules,
+    /// The weights used for [`Density::Painted`]
+    mask: Option<Array2<f32>>,
     rng: SmallRng,
     is_new: bool,
+    first_click: Option<(usize, usize)>,
 }
 
 impl Field {
@@ -14,28 +93,72 @@ impl Field {
         self.board.dim()
     }
 
+    pub(crate) fn rules(&self) -> Rules {
+        self.rules
+    }
+
     pub(crate) fn complete(&self) -> bool {
         // The game is complete when we have no more unrevealed (or flagged) spaces that are not mines
         !self.board.iter().any(|cell| {
-            matches!(cell.state, CellState::Unrevealed | CellState::Flagged) && !cell.mine
+            (cell.state.is_unrevealed() || cell.state == CellState::Flagged) && !cell.has_mine()
         })
     }
 
-    /// Returns the number of total mines minus the number of total flags
+    pub(crate) fn total_mines(&self) -> usize {
+        self.board.iter().map(|cell| cell.mines as usize).sum()
+    }
+
+    /// Returns the number of total mines minus the number of total flags (and exploded mines)
     pub(crate) fn remaining_mines(&self) -> usize {
-        let mines = self.board.iter().filter(|cell| cell.mine).count();
+        let mines = self.total_mines();
         let flags = self
             .board
             .iter()
-            .filter(|cell| cell.state == CellState::Flagged)
-            .count();
+            .map(|cell| cell.marked_mines() as usize)
+            .sum::<usize>();
         mines.saturating_sub(flags)
     }
 
+    /// How many more mines can explode before the game is over. The game is over when this is 0.
+    pub(crate) fn lives_left(&self) -> u8 {
+        let exploded = self
+            .board
+            .iter()
+            .filter(|cell| cell.state == CellState::Exploded)
+            .count();
+        (self.rules.lives as usize).saturating_sub(exploded) as u8
+    }
+
+    /// Returns the number of cells that have been revealed (including exploded mines).
+    pub(crate) fn revealed_count(&self) -> usize {
+        self.board
+            .iter()
+            .filter(|cell| {
+                matches!(
+                    cell.state,
+                    CellState::Revealed | CellState::Empty | CellState::Exploded
+                )
+            })
+            .count()
+    }
+
+    /// The first cell that was cleared on this board, if any. The board is fixed from this point
+    /// on.
+    pub(crate) fn first_click(&self) -> Option<(usize, usize)> {
+        self.first_click
+    }
+
     /// Returns None if either dimension was zero, or too many mines were specified than can (reasonably)
-    /// fit on the board.
-    pub(crate) fn new(size: (usize, usize), mines: usize) -> Option<Self> {
-        if size.0 == 0 || size.1 == 0 || mines > (size.0 * size.1 + 1) / 2 {
+    /// fit on the board. Even when cells can hold several mines, there can't be more mines than
+    /// cells, so that the first click has room to be safe.
+    pub(crate) fn new(size: (usize, usize), mines: usize, rules: Rules) -> Option<Self> {
+        let cells = size.0 * size.1;
+        let capacity = cells * rules.mines_per_cell as usize;
+        if size.0 == 0
+            || size.1 == 0
+            || !(1..=MAX_MINES_PER_CELL).contains(&rules.mines_per_cell)
+            || mines > ((capacity + 1) / 2).min(cells)
+        {
             return None;
         }
 
@@ -44,55 +167,237 @@ impl Field {
         let mut field = Self {
             board,
             mines,
+            rules,
+            mask: None,
             rng,
             is_new: true,
+            first_click: None,
         };
 
-        field.init_board();
+        field.init_board(&[]);
         Some(field)
     }
 
+    /// Returns a field with mines in exactly the given cells. Unlike a new field, the first click
+    /// will not move them.
+    pub(crate) fn from_mines(size: (usize, usize), mines: &[(usize, usize)]) -> Self {
+        let mut field = Self {
+            board: Array2::default(size),
+            mines: mines.len(),
+            rules: Rules::default(),
+            mask: None,
+            rng: SmallRng::seed_from_u64(0),
+            is_new: false,
+            first_click: None,
+        };
+        for pos in mines {
+            field.place_mine(*pos);
+        }
+        field
+    }
+
+    /// Regenerates the field with mines placed according to the given weights, if it uses
+    /// [`Density::Painted`]. The mask must be the same size as the field.
+    pub(crate) fn with_mask(mut self, mask: Array2<f32>) -> Self {
+        if self.rules.density == Density::Painted && mask.dim() == self.size() {
+            self.mask = Some(mask);
+            self.clear();
+        }
+        self
+    }
+
     pub(crate) fn clear(&mut self) {
         self.is_new = true;
+        self.first_click = None;
+        self.board.fill(Default::default());
+        self.init_board(&[]);
+    }
+
+    /// Generates a new board where clearing `pos` first is safe. Mines are kept out of the cell and
+    /// its neighbors if the rest of the board can hold them all, so that it opens up, or else out
+    /// of just the cell. A board too full for even that is left to chance.
+    fn clear_around(&mut self, pos: (usize, usize)) {
+        let around = iter::once(pos)
+            .chain(neighbors(&self.board, pos))
+            .collect::<Vec<_>>();
+        let safe = [&around[..], &around[..1]]
+            .into_iter()
+            .find(|safe| {
+                let room = (self.board.len() - safe.len()) * self.rules.mines_per_cell as usize;
+                self.mines <= room
+            })
+            .unwrap_or_default();
+
         self.board.fill(Default::default());
-        self.init_board();
+        self.init_board(safe);
     }
 
-    fn init_board(&mut self) {
+    /// Generates a new board, then clears the same first cell as on the current board (if any).
+    pub(crate) fn regenerate(&mut self) {
+        let first_click = self.first_click;
+        self.clear();
+        if let Some(pos) = first_click {
+            self.clear_cell(pos);
+        }
+    }
+
+    /// Returns a copy of this field with every cell hidden again, except for those revealed by the
+    /// first click.
+    pub(crate) fn replay_first_click(&self) -> Self {
+        let mut field = self.clone();
+        for cell in field.board.iter_mut() {
+            cell.state = CellState::Unrevealed;
+            cell.flags = 0;
+        }
+        if let Some(pos) = self.first_click {
+            field.is_new = false;
+            field.clear_cell(pos);
+        }
+        field
+    }
+
+    /// Places the field's mines, keeping them out of the `safe` cells.
+    fn init_board(&mut self, safe: &[(usize, usize)]) {
         let x_d = Uniform::new(0, self.size().0);
         let y_d = Uniform::new(0, self.size().1);
 
         let mut placed_mines = 0;
+        if let Some(weights) = self.density_weights() {
+            placed_mines = self.place_weighted_mines(weights, safe);
+        }
         while placed_mines < self.mines {
             let mine_pos = (self.rng.sample(x_d), self.rng.sample(y_d));
-            if self.board[mine_pos].mine {
+            if self.board[mine_pos].mines >= self.rules.mines_per_cell || safe.contains(&mine_pos) {
                 continue;
             }
 
-            self.board[mine_pos].mine = true;
+            self.place_mine(mine_pos);
+            placed_mines += 1;
+        }
+
+        if self.rules.liar {
+            self.place_lies();
+        }
+    }
+
+    /// Moves every number up or down by one at random, staying within what the cell's neighbors
+    /// could hold.
+    fn place_lies(&mut self) {
+        let positions = self
+            .board
+            .indexed_iter()
+            .map(|(pos, _)| pos)
+            .collect::<Vec<_>>();
+        for pos in positions {
+            let most = neighbors(&self.board, pos).count() as u8 * self.rules.mines_per_cell;
+            let cell = &mut self.board[pos];
+            cell.offset = match cell.neighbors {
+                0 => 0,
+                n if n == most => -1,
+                _ if self.rng.gen() => 1,
+                _ => -1,
+            };
+        }
+    }
+
+    /// The weight of each cell for the field's mine density, or None if it is uniform.
+    fn density_weights(&mut self) -> Option<Array2<f32>> {
+        let (width, height) = self.size();
+        match self.rules.density {
+            Density::Uniform => None,
+            Density::Clustered => {
+                let clusters = (width * height / CELLS_PER_CLUSTER).max(1);
+                let centers = (0..clusters)
+                    .map(|_| {
+                        (
+                            self.rng.gen_range(0.0..width as f32),
+                            self.rng.gen_range(0.0..height as f32),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                Some(Array2::from_shape_fn(self.size(), |(x, y)| {
+                    CLUSTER_BACKGROUND
+                        + centers
+                            .iter()
+                            .map(|(cx, cy)| {
+                                let distance_sq =
+                                    (x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2);
+                                (-distance_sq / (2.0 * CLUSTER_SPREAD.powi(2))).exp()
+                            })
+                            .sum::<f32>()
+                }))
+            }
+            Density::Gradient => Some(Array2::from_shape_fn(self.size(), |(x, _)| {
+                (x as f32 + 0.5) / width as f32
+            })),
+            Density::Painted => self.mask.clone(),
+        }
+    }
 
-            for neighbor in neighbors(&self.board, mine_pos) {
-                self.board[neighbor].neighbors += 1;
+    /// Places mines with the given weights, until every cell with any weight is full. The `safe`
+    /// cells are left out. Returns the number of mines placed.
+    fn place_weighted_mines(&mut self, weights: Array2<f32>, safe: &[(usize, usize)]) -> usize {
+        let positions = self
+            .board
+            .indexed_iter()
+            .map(|(pos, _)| pos)
+            .collect::<Vec<_>>();
+        let weights = weights.indexed_iter().map(|(pos, weight)| {
+            if safe.contains(&pos) {
+                0.0
+            } else {
+                weight.max(0.0)
             }
+        });
+        let Ok(mut distribution) = WeightedIndex::new(weights) else {
+            return 0;
+        };
 
+        let mut placed_mines = 0;
+        while placed_mines < self.mines {
+            let i = distribution.sample(&mut self.rng);
+            let pos = positions[i];
+            self.place_mine(pos);
             placed_mines += 1;
+
+            // Once every weighted cell is full, the rest go anywhere
+            if self.board[pos].mines >= self.rules.mines_per_cell
+                && distribution.update_weights(&[(i, &0.0)]).is_err()
+            {
+                break;
+            }
+        }
+        placed_mines
+    }
+
+    fn place_mine(&mut self, pos: (usize, usize)) {
+        self.board[pos].mines += 1;
+        for neighbor in neighbors(&self.board, pos) {
+            self.board[neighbor].neighbors += 1;
         }
     }
 
     /// Returns a bool signifying if a mine has exploded. Returns None if the given cell has already
-    /// been cleared or flagged, or if the given cell is invalid.
+    /// been cleared or flagged, or if the given cell is invalid. On a new field, the board is
+    /// regenerated first if the cell would not open up.
     pub(crate) fn clear_cell(&mut self, pos: (usize, usize)) -> Option<bool> {
-        match (self.is_new, self.board.get_mut(pos)?.reveal()?) {
-            (_, RevealStatus::Empty) => {}
-            (true, _) => {
-                self.clear();
-                return self.clear_cell(pos);
-            }
-            (false, RevealStatus::Exploded) => return Some(true),
-            (false, RevealStatus::Safe) => return Some(false),
+        let cell = self.board.get(pos)?;
+        if self.is_new && cell.state.is_unrevealed() && (cell.has_mine() || cell.neighbors > 0) {
+            self.clear_around(pos);
         }
 
+        let status = self.board[pos].reveal()?;
         self.is_new = false;
+        match status {
+            RevealStatus::Empty => {}
+            RevealStatus::Exploded => return Some(true),
+            RevealStatus::Safe => {
+                self.first_click.get_or_insert(pos);
+                return Some(false);
+            }
+        }
+
+        self.first_click.get_or_insert(pos);
 
         // If the cell was empty, clear neighboring empty cells
         let mut check = neighbors(&self.board, pos).collect::<Vec<_>>();
@@ -106,28 +411,110 @@ impl Field {
         Some(false)
     }
 
-    /// Returns a bool signifying that the flag was valid (i.e., that the cell was not already
-    /// revealed). Returns None if the cell was invalid.
-    pub(crate) fn toggle_flag(&mut self, pos: (usize, usize)) -> Option<bool> {
+    /// Flags an unrevealed cell, or unflags a flagged one. If `question_marks` is set, flags become
+    /// question marks before going back to unrevealed. When cells can hold more than one mine, flags
+    /// are stacked until the cell has as many as it can hold. Returns a bool signifying that the
+    /// flag was valid (i.e., that the cell was not already revealed). Returns None if the cell was
+    /// invalid.
+    pub(crate) fn cycle_mark(&mut self, pos: (usize, usize), question_marks: bool) -> Option<bool> {
+        let max_flags = self.rules.mines_per_cell;
         self.board.get_mut(pos).map(|cell| {
             self.is_new = false;
-            cell.toggle_flag()
+            cell.cycle_mark(question_marks, max_flags)
         })
     }
 
+    /// Sets the number of flags on an unrevealed or flagged cell, unflagging it if there are none.
+    pub(crate) fn set_flags(&mut self, pos: (usize, usize), flags: u8) {
+        let cell = &mut self.board[pos];
+        if cell.state.is_unrevealed() || cell.state == CellState::Flagged {
+            cell.set_flags(flags);
+        }
+    }
+
+    /// Flags every mine that is not already flagged.
+    pub(crate) fn flag_mines(&mut self) {
+        for cell in self.board.iter_mut() {
+            if cell.has_mine() && (cell.state.is_unrevealed() || cell.state == CellState::Flagged) {
+                cell.set_flags(cell.mines);
+            }
+        }
+    }
+
+    /// The fewest and most mines there could be around a revealed number. These are the same unless
+    /// the numbers can lie.
+    pub(crate) fn mine_range(&self, pos: (usize, usize)) -> (usize, usize) {
+        let number = self.board[pos].number() as usize;
+        match number {
+            _ if !self.rules.liar => (number, number),
+            0 => (1, 1),
+            _ => (number - 1, number + 1),
+        }
+    }
+
+    /// The number of mines the player knows are around a cell. Cells in `inferred` are counted as
+    /// full of mines if they are unrevealed.
+    fn marked_neighbors(&self, pos: (usize, usize), inferred: Option<&Array2<bool>>) -> usize {
+        neighbors(&self.board, pos)
+            .map(|pos| {
+                let cell = self.board[pos];
+                if cell.state.is_unrevealed() && inferred.is_some_and(|inferred| inferred[pos]) {
+                    self.rules.mines_per_cell as usize
+                } else {
+                    cell.marked_mines() as usize
+                }
+            })
+            .sum()
+    }
+
+    /// Fills every unrevealed neighbor of a revealed number with flags, if they must all be full of
+    /// mines. Returns whether any were flagged.
+    pub(crate) fn flag_neighbors(&mut self, pos: (usize, usize)) -> bool {
+        if self.board.get(pos).map(|cell| cell.state) != Some(CellState::Revealed) {
+            return false;
+        }
+        let hidden = neighbors(&self.board, pos)
+            .filter(|pos| self.board[*pos].state.is_unrevealed())
+            .collect::<Vec<_>>();
+        let needed = self
+            .mine_range(pos)
+            .0
+            .checked_sub(self.marked_neighbors(pos, None));
+        if hidden.is_empty() || needed != Some(hidden.len() * self.rules.mines_per_cell as usize) {
+            return false;
+        }
+
+        for pos in hidden {
+            self.set_flags(pos, self.rules.mines_per_cell);
+        }
+        true
+    }
+
+    /// Clears every unflagged neighbor of a revealed number that has as many flags around it as it
+    /// could have mines. Returns None if the cell is not such a number.
     pub(crate) fn clear_neighbors(&mut self, pos: (usize, usize)) -> Option<bool> {
+        self.clear_neighbors_inferred(pos, None)
+    }
+
+    /// Like [`Self::clear_neighbors`], but cells in `inferred` are counted as mines too, and left
+    /// hidden. This is for playing without flags.
+    pub(crate) fn clear_neighbors_inferred(
+        &mut self,
+        pos: (usize, usize),
+        inferred: Option<&Array2<bool>>,
+    ) -> Option<bool> {
         let cell = self.board.get(pos)?;
         if cell.state != CellState::Revealed
-            || neighbors(&self.board, pos)
-                .filter(|pos| self.board[*pos].state == CellState::Flagged)
-                .count()
-                != cell.neighbors as usize
+            || self.marked_neighbors(pos, inferred) != self.mine_range(pos).1
         {
             return None;
         }
 
         let mut exploded = false;
         for pos in neighbors(&self.board, pos) {
+            if inferred.is_some_and(|inferred| inferred[pos]) {
+                continue;
+            }
             if self.clear_cell(pos).unwrap_or_default() {
                 exploded = true;
             }
@@ -160,7 +547,12 @@ pub(crate) fn neighbors<T>(
 pub(crate) struct Cell {
     pub(crate) state: CellState,
     pub(crate) neighbors: u8,
-    mine: bool,
+    /// The number of mines in this cell, which is at most one unless the rules allow more
+    pub(crate) mines: u8,
+    /// The number of flags stacked on this cell, which is nonzero only when it is flagged
+    pub(crate) flags: u8,
+    /// How far the shown number is from the real one, which is only nonzero in liar mode
+    pub(crate) offset: i8,
 }
 
 impl Default for Cell {
@@ -168,24 +560,53 @@ impl Default for Cell {
         Self {
             state: CellState::Unrevealed,
             neighbors: 0,
-            mine: false,
+            mines: 0,
+            flags: 0,
+            offset: 0,
         }
     }
 }
 
 impl Cell {
+    pub(crate) fn has_mine(&self) -> bool {
+        self.mines > 0
+    }
+
+    /// The number of mines the player knows are in this cell, from flags or from exploding.
+    pub(crate) fn marked_mines(&self) -> u8 {
+        match self.state {
+            CellState::Flagged => self.flags,
+            CellState::Exploded => self.mines,
+            _ => 0,
+        }
+    }
+
+    /// The number shown on the cell once it is revealed.
+    pub(crate) fn number(&self) -> u8 {
+        self.neighbors.wrapping_add_signed(self.offset)
+    }
+
+    fn set_flags(&mut self, flags: u8) {
+        self.flags = flags;
+        self.state = if flags == 0 {
+            CellState::Unrevealed
+        } else {
+            CellState::Flagged
+        };
+    }
+
     /// Returns None if the cell has already been cleared or flagged.
     fn reveal(&mut self) -> Option<RevealStatus> {
         match self.state {
-            CellState::Unrevealed if self.mine => {
+            state if state.is_unrevealed() && self.has_mine() => {
                 self.state = CellState::Exploded;
                 Some(RevealStatus::Exploded)
             }
-            CellState::Unrevealed if self.neighbors == 0 => {
+            state if state.is_unrevealed() && self.neighbors == 0 => {
                 self.state = CellState::Empty;
                 Some(RevealStatus::Empty)
             }
-            CellState::Unrevealed => {
+            state if state.is_unrevealed() => {
                 self.state = CellState::Revealed;
                 Some(RevealStatus::Safe)
             }
@@ -195,19 +616,40 @@ impl Cell {
 
     /// Returns a bool signifying that the flag was valid (i.e., that the cell was not already
     /// revealed).
-    fn toggle_flag(&mut self) -> bool {
+    fn toggle_flag(&mut self, max_flags: u8) -> bool {
         match self.state {
-            CellState::Unrevealed => {
-                self.state = CellState::Flagged;
+            CellState::Unrevealed | CellState::Questioned => {
+                self.set_flags(1);
+                true
+            }
+            CellState::Flagged if self.flags < max_flags => {
+                self.set_flags(self.flags + 1);
                 true
             }
             CellState::Flagged => {
-                self.state = CellState::Unrevealed;
+                self.set_flags(0);
                 true
             }
             _ => false,
         }
     }
+
+    /// Like [`Self::toggle_flag`], but if `question_marks` is set, flags become question marks
+    /// before going back to unrevealed.
+    fn cycle_mark(&mut self, question_marks: bool, max_flags: u8) -> bool {
+        match self.state {
+            CellState::Flagged if question_marks && self.flags >= max_flags => {
+                self.flags = 0;
+                self.state = CellState::Questioned;
+                true
+            }
+            CellState::Questioned => {
+                self.state = CellState::Unrevealed;
+                true
+            }
+            _ => self.toggle_flag(max_flags),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -216,6 +658,8 @@ pub(crate) enum CellState {
     Unrevealed,
     /// Flagged
     Flagged,
+    /// Marked with a question mark, otherwise the same as unrevealed
+    Questioned,
     /// Clicked on, showing a number
     Revealed,
     /// Clicked on, was a mine
@@ -224,9 +668,82 @@ pub(crate) enum CellState {
     Empty,
 }
 
+impl CellState {
+    /// Whether the cell is unrevealed and not flagged, whether or not it has a question mark.
+    pub(crate) fn is_unrevealed(self) -> bool {
+        matches!(self, Self::Unrevealed | Self::Questioned)
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum RevealStatus {
     Exploded,
     Safe,
     Empty,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn too_many_mines() {
+        let rules = Rules {
+            mines_per_cell: MAX_MINES_PER_CELL,
+            ..Rules::default()
+        };
+        assert!(Field::new((25, 25), 625, rules).is_some());
+        assert!(Field::new((25, 25), 626, rules).is_none());
+        assert!(Field::new((9, 9), 41, Rules::default()).is_some());
+        assert!(Field::new((9, 9), 42, Rules::default()).is_none());
+    }
+
+    #[test]
+    fn first_click_opens_up() {
+        for mines_per_cell in 1..=MAX_MINES_PER_CELL {
+            let rules = Rules {
+                mines_per_cell,
+                ..Rules::default()
+            };
+            let mines = if mines_per_cell == 1 { 313 } else { 625 };
+            for pos in [(12, 12), (0, 0), (24, 7)] {
+                let mut field = Field::new((25, 25), mines, rules).unwrap();
+                assert_eq!(field.clear_cell(pos), Some(false));
+                assert_eq!(field.board[pos].state, CellState::Empty);
+                assert_eq!(field.total_mines(), mines);
+                assert_eq!(field.first_click(), Some(pos));
+            }
+        }
+    }
+
+    #[test]
+    fn first_click_next_to_painted_cells() {
+        let rules = Rules {
+            density: Density::Painted,
+            ..Rules::default()
+        };
+        // Only the corner is painted, so mines would always go next to a click beside it first
+        let mut mask = Array2::zeros((9, 9));
+        mask.slice_mut(ndarray::s![..2, ..2]).fill(1.0);
+        for density in Density::ALL {
+            let rules = Rules { density, ..rules };
+            for pos in [(0, 0), (1, 1), (2, 2), (4, 4)] {
+                let mut field = Field::new((9, 9), 10, rules)
+                    .unwrap()
+                    .with_mask(mask.clone());
+                assert_eq!(field.clear_cell(pos), Some(false));
+                assert_eq!(field.board[pos].state, CellState::Empty);
+                assert_eq!(field.total_mines(), 10);
+            }
+        }
+    }
+
+    #[test]
+    fn first_click_on_a_full_board() {
+        // There's no room to keep mines away from the first click's neighbors, only the cell itself
+        let mut field = Field::new((2, 2), 2, Rules::default()).unwrap();
+        assert_eq!(field.clear_cell((0, 0)), Some(false));
+        assert_eq!(field.board[(0, 0)].state, CellState::Revealed);
+        assert_eq!(field.total_mines(), 2);
+    }
+}
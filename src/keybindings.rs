@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use egui::{InputState, Key};
+
+/// Everything that can be done from the keyboard.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize,
+)]
+pub(crate) enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Deselect,
+    Reveal,
+    Flag,
+    Chord,
+    NewGame,
+    Undo,
+    Hint,
+    TogglePredictions,
+    ToggleFullscreen,
+}
+
+impl Action {
+    pub(crate) const ALL: [Self; 13] = [
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::Deselect,
+        Self::Reveal,
+        Self::Flag,
+        Self::Chord,
+        Self::NewGame,
+        Self::Undo,
+        Self::Hint,
+        Self::TogglePredictions,
+        Self::ToggleFullscreen,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::MoveUp => "Move up",
+            Self::MoveDown => "Move down",
+            Self::MoveLeft => "Move left",
+            Self::MoveRight => "Move right",
+            Self::Deselect => "Deselect",
+            Self::Reveal => "Reveal",
+            Self::Flag => "Flag",
+            Self::Chord => "Chord",
+            Self::NewGame => "New game",
+            Self::Undo => "Undo",
+            Self::Hint => "Hint",
+            Self::TogglePredictions => "Toggle predictions",
+            Self::ToggleFullscreen => "Toggle fullscreen",
+        }
+    }
+
+    fn default_keys(self) -> Vec<Key> {
+        match self {
+            Self::MoveUp => vec![Key::ArrowUp, Key::W, Key::K],
+            Self::MoveDown => vec![Key::ArrowDown, Key::S, Key::J],
+            Self::MoveLeft => vec![Key::ArrowLeft, Key::A, Key::H],
+            Self::MoveRight => vec![Key::ArrowRight, Key::D, Key::L],
+            Self::Deselect => vec![Key::Escape],
+            Self::Reveal => vec![Key::Space],
+            Self::Flag => vec![Key::F],
+            Self::Chord => vec![Key::C],
+            Self::NewGame => vec![Key::R],
+            Self::Undo => vec![Key::U, Key::Z],
+            Self::Hint => vec![Key::Questionmark, Key::I],
+            Self::TogglePredictions => vec![Key::P],
+            Self::ToggleFullscreen => vec![Key::F11],
+        }
+    }
+}
+
+/// The keys bound to each [`Action`]. Any of an action's keys will trigger it.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct KeyBindings {
+    bindings: BTreeMap<Action, Vec<Key>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_keys()))
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub(crate) fn keys(&self, action: Action) -> &[Key] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns true if any of the keys bound to the given action were pressed this frame.
+    pub(crate) fn pressed(&self, input: &InputState, action: Action) -> bool {
+        self.keys(action).iter().any(|key| input.key_pressed(*key))
+    }
+
+    /// Adds a key to an action, removing it from any other action it was bound to.
+    pub(crate) fn bind(&mut self, action: Action, key: Key) {
+        for keys in self.bindings.values_mut() {
+            keys.retain(|k| *k != key);
+        }
+        self.bindings.entry(action).or_default().push(key);
+    }
+
+    /// Gives the default keys to any actions that were missing from saved bindings (e.g., ones added
+    /// since they were saved).
+    pub(crate) fn add_missing_defaults(&mut self) {
+        for action in Action::ALL {
+            self.bindings
+                .entry(action)
+                .or_insert_with(|| action.default_keys());
+        }
+    }
+
+    pub(crate) fn unbind(&mut self, action: Action, key: Key) {
+        if let Some(keys) = self.bindings.get_mut(&action) {
+            keys.retain(|k| *k != key);
+        }
+    }
+}
@@ -1,6 +1,14 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+mod difficulty;
 mod game;
+mod keybindings;
+mod puzzle;
 mod solver;
+mod theme;
 pub use app::Minesweeper;
+
+// Only public so that the benchmarks can use it
+#[doc(hidden)]
+pub use solver::bitgrid;
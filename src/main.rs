@@ -8,7 +8,7 @@ fn main() -> eframe::Result<()> {
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([400.0, 300.0])
+            .with_inner_size([800.0, 600.0])
             .with_min_inner_size([300.0, 220.0])
             .with_icon(
                 // NOE: Adding an icon is optional
@@ -0,0 +1,381 @@
+use ndarray::Array2;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+    difficulty::Difficulty,
+    game::{Cell, CellState, Field, Rules},
+    solver::{predict_single, SolverChoice},
+};
+
+/// How many boards to try generating before giving up on finding a training puzzle.
+const GENERATE_ATTEMPTS: usize = 20;
+/// The most rounds of easy deductions played out on a generated board before it is used as a
+/// puzzle.
+const MAX_WARMUP_STEPS: usize = 4;
+
+/// A hand-made starting position: where the mines are, and which cells start revealed.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct Puzzle {
+    pub(crate) name: String,
+    pub(crate) size: (usize, usize),
+    pub(crate) mines: Vec<(usize, usize)>,
+    /// The cells that are revealed at the start. Empty cells open up their neighbors as usual.
+    pub(crate) revealed: Vec<(usize, usize)>,
+}
+
+impl Puzzle {
+    pub(crate) fn new(size: (usize, usize)) -> Self {
+        Self {
+            name: String::new(),
+            size,
+            mines: Vec::new(),
+            revealed: Vec::new(),
+        }
+    }
+
+    /// Makes a puzzle from the cells revealed on a field so far.
+    pub(crate) fn from_field(name: String, field: &Field) -> Self {
+        let cells = |f: fn(&Cell) -> bool| {
+            field
+                .board
+                .indexed_iter()
+                .filter(|(_, cell)| f(cell))
+                .map(|(pos, _)| pos)
+                .collect()
+        };
+        Self {
+            name,
+            size: field.size(),
+            mines: cells(Cell::has_mine),
+            revealed: cells(|cell| matches!(cell.state, CellState::Revealed | CellState::Empty)),
+        }
+    }
+
+    /// Generates a random training puzzle: a board that has been opened up and played for a few
+    /// easy steps, that still has at least one cell the solver can prove safe. Returns None if no
+    /// such board was found.
+    pub(crate) fn generate(size: (usize, usize), mines: usize) -> Option<Self> {
+        let mut rng = SmallRng::from_entropy();
+        for _ in 0..GENERATE_ATTEMPTS {
+            let mut field = Field::new(size, mines, Rules::default())?;
+            field.clear_cell((size.0 / 2, size.1 / 2));
+            for _ in 0..rng.gen_range(0..=MAX_WARMUP_STEPS) {
+                let single = predict_single(&field);
+                for (pos, prob) in single.indexed_iter() {
+                    if *prob == Some(0.0) && field.board[pos].state.is_unrevealed() {
+                        field.clear_cell(pos);
+                    }
+                }
+            }
+
+            if !field.complete() && safe_cells(&field).iter().any(|safe| *safe) {
+                let name = format!("Training {}x{}", size.0, size.1);
+                return Some(Self::from_field(name, &field));
+            }
+        }
+        None
+    }
+
+    /// The field at the start of the puzzle.
+    pub(crate) fn field(&self) -> Field {
+        let mut field = Field::from_mines(self.size, &self.mines);
+        for pos in &self.revealed {
+            field.clear_cell(*pos);
+        }
+        field
+    }
+
+    /// Rates the puzzle by playing it out with the solver from its starting position.
+    pub(crate) fn difficulty(&self) -> Difficulty {
+        Difficulty::rate_position(self.field())
+    }
+
+    /// Adds or removes a mine. A cell can't be both a mine and revealed.
+    pub(crate) fn toggle_mine(&mut self, pos: (usize, usize)) {
+        self.revealed.retain(|revealed| *revealed != pos);
+        toggle(&mut self.mines, pos);
+    }
+
+    /// Adds or removes a revealed cell. A cell can't be both a mine and revealed.
+    pub(crate) fn toggle_revealed(&mut self, pos: (usize, usize)) {
+        self.mines.retain(|mine| *mine != pos);
+        toggle(&mut self.revealed, pos);
+    }
+
+    /// Changes the size of the board, dropping any cells that no longer fit.
+    pub(crate) fn resize(&mut self, size: (usize, usize)) {
+        self.size = size;
+        let fits = |(x, y): &(usize, usize)| *x < size.0 && *y < size.1;
+        self.mines.retain(fits);
+        self.revealed.retain(fits);
+    }
+}
+
+fn toggle(cells: &mut Vec<(usize, usize)>, pos: (usize, usize)) {
+    match cells.iter().position(|cell| *cell == pos) {
+        Some(i) => {
+            cells.remove(i);
+        }
+        None => cells.push(pos),
+    }
+}
+
+/// The unrevealed cells that can be proven safe.
+fn safe_cells(field: &Field) -> Array2<bool> {
+    let predictions = SolverChoice::Exact.solver().predict(field);
+    let mut safe = predictions.mapv(|prob| prob == Some(0.0));
+    for (pos, cell) in field.board.indexed_iter() {
+        safe[pos] &= cell.state.is_unrevealed();
+    }
+    safe
+}
+
+/// What the player has to find in puzzle mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum Goal {
+    /// Every cell that can be proven safe
+    AllSafe,
+    /// Any single cell that can be proven safe
+    OneSafe,
+}
+
+impl Goal {
+    pub(crate) const ALL: [Self; 2] = [Self::AllSafe, Self::OneSafe];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::AllSafe => "Find all safe cells",
+            Self::OneSafe => "Find a safe cell",
+        }
+    }
+}
+
+/// A puzzle being solved in puzzle mode. Instead of revealing cells, the player picks the cells
+/// they can prove are safe, and the picks are checked against the solver.
+#[derive(Debug, Clone)]
+pub(crate) struct Round {
+    pub(crate) goal: Goal,
+    pub(crate) picks: Vec<(usize, usize)>,
+    safe: Array2<bool>,
+    /// Set once the picks have been submitted.
+    pub(crate) score: Option<Score>,
+}
+
+/// How well the player's picks matched the cells that can be proven safe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Score {
+    /// Picks that can be proven safe
+    pub(crate) correct: usize,
+    /// Picks that can't be proven safe, whether or not they are
+    pub(crate) wrong: usize,
+    /// Safe cells that still needed to be picked for the goal
+    pub(crate) missed: usize,
+}
+
+impl Score {
+    pub(crate) fn passed(&self) -> bool {
+        self.wrong == 0 && self.missed == 0
+    }
+}
+
+impl Round {
+    pub(crate) fn new(field: &Field, goal: Goal) -> Self {
+        Self {
+            goal,
+            picks: Vec::new(),
+            safe: safe_cells(field),
+            score: None,
+        }
+    }
+
+    /// Picks or unpicks an unrevealed cell. With [`Goal::OneSafe`], picking a cell replaces the
+    /// previous pick.
+    pub(crate) fn toggle_pick(&mut self, field: &Field, pos: (usize, usize)) {
+        if self.score.is_some() || !field.board[pos].state.is_unrevealed() {
+            return;
+        }
+        if let Some(i) = self.picks.iter().position(|pick| *pick == pos) {
+            self.picks.remove(i);
+        } else {
+            if self.goal == Goal::OneSafe {
+                self.picks.clear();
+            }
+            self.picks.push(pos);
+        }
+    }
+
+    pub(crate) fn submit(&mut self) -> Score {
+        let correct = self.picks.iter().filter(|pos| self.safe[**pos]).count();
+        let missed = match self.goal {
+            Goal::AllSafe => self.safe.iter().filter(|safe| **safe).count() - correct,
+            Goal::OneSafe => (correct == 0) as usize,
+        };
+        let score = Score {
+            correct,
+            wrong: self.picks.len() - correct,
+            missed,
+        };
+        self.score = Some(score);
+        score
+    }
+
+    /// Whether the cell can be proven safe.
+    pub(crate) fn is_safe(&self, pos: (usize, usize)) -> bool {
+        self.safe[pos]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn puzzle(
+        size: (usize, usize),
+        mines: &[(usize, usize)],
+        revealed: &[(usize, usize)],
+    ) -> Puzzle {
+        Puzzle {
+            name: "test".to_owned(),
+            size,
+            mines: mines.to_vec(),
+            revealed: revealed.to_vec(),
+        }
+    }
+
+    #[test]
+    fn field() {
+        let field = puzzle((4, 3), &[(3, 2)], &[(0, 0)]).field();
+        assert_eq!(field.size(), (4, 3));
+        assert_eq!(field.total_mines(), 1);
+        assert!(field.board[(3, 2)].has_mine());
+        // The empty cell opens up everything but the mine
+        for (pos, cell) in field.board.indexed_iter() {
+            let expected = match pos {
+                (3, 2) => CellState::Unrevealed,
+                (2, 1) | (3, 1) | (2, 2) => CellState::Revealed,
+                _ => CellState::Empty,
+            };
+            assert_eq!(cell.state, expected, "cell {pos:?}");
+        }
+
+        // A revealed number doesn't open up anything else
+        let field = puzzle((3, 1), &[(0, 0)], &[(1, 0)]).field();
+        assert_eq!(field.board[(1, 0)].state, CellState::Revealed);
+        assert_eq!(field.board[(2, 0)].state, CellState::Unrevealed);
+    }
+
+    #[test]
+    fn toggle() {
+        let mut puzzle = puzzle((3, 3), &[], &[]);
+        puzzle.toggle_mine((0, 0));
+        puzzle.toggle_mine((1, 1));
+        assert_eq!(puzzle.mines, [(0, 0), (1, 1)]);
+        puzzle.toggle_mine((0, 0));
+        assert_eq!(puzzle.mines, [(1, 1)]);
+
+        // A cell can't be both a mine and revealed
+        puzzle.toggle_revealed((1, 1));
+        assert_eq!(puzzle.mines, []);
+        assert_eq!(puzzle.revealed, [(1, 1)]);
+        puzzle.toggle_mine((1, 1));
+        assert_eq!(puzzle.mines, [(1, 1)]);
+        assert_eq!(puzzle.revealed, []);
+    }
+
+    #[test]
+    fn resize() {
+        let mut puzzle = puzzle((4, 4), &[(0, 3), (3, 0)], &[(1, 1), (2, 3)]);
+        puzzle.resize((4, 3));
+        assert_eq!(puzzle.size, (4, 3));
+        assert_eq!(puzzle.mines, [(3, 0)]);
+        assert_eq!(puzzle.revealed, [(1, 1)]);
+        puzzle.resize((2, 5));
+        assert_eq!(puzzle.mines, []);
+        assert_eq!(puzzle.revealed, [(1, 1)]);
+        assert_eq!(puzzle.field().size(), (2, 5));
+    }
+
+    #[test]
+    fn solvability() {
+        let solvable = puzzle((4, 3), &[(3, 2)], &[(0, 0)]);
+        assert_eq!(solvable.difficulty().guesses, 0);
+
+        // Nothing tells the two cells apart
+        let coin_flip = puzzle((2, 1), &[(0, 0)], &[]);
+        assert!(coin_flip.difficulty().guesses > 0);
+
+        // The mine could be on either side of the 1
+        let fifty_fifty = puzzle((3, 1), &[(0, 0)], &[(1, 0)]);
+        assert!(fifty_fifty.difficulty().guesses > 0);
+    }
+
+    /// A round where the 1s prove the right column safe, but the mine could be either of the two
+    /// cells under the left 1.
+    fn round(goal: Goal, picks: &[(usize, usize)]) -> Round {
+        let field = puzzle((3, 2), &[(0, 1)], &[(0, 0), (1, 0)]).field();
+        let mut round = Round::new(&field, goal);
+        for pick in picks {
+            round.toggle_pick(&field, *pick);
+        }
+        round
+    }
+
+    #[test]
+    fn submit_all_safe() {
+        let score = round(Goal::AllSafe, &[(2, 0), (2, 1)]).submit();
+        assert_eq!(
+            score,
+            Score {
+                correct: 2,
+                wrong: 0,
+                missed: 0
+            }
+        );
+        assert!(score.passed());
+
+        let score = round(Goal::AllSafe, &[(2, 0)]).submit();
+        assert_eq!(score.missed, 1);
+        assert!(!score.passed());
+
+        // Picking the mine is wrong
+        let score = round(Goal::AllSafe, &[(2, 0), (2, 1), (0, 1)]).submit();
+        assert_eq!((score.correct, score.wrong), (2, 1));
+        assert!(!score.passed());
+
+        // So is a cell that happens to be safe, but can't be proven to be
+        let score = round(Goal::AllSafe, &[(2, 0), (2, 1), (1, 1)]).submit();
+        assert_eq!((score.correct, score.wrong), (2, 1));
+        assert!(!score.passed());
+    }
+
+    #[test]
+    fn submit_one_safe() {
+        assert!(round(Goal::OneSafe, &[(2, 1)]).submit().passed());
+
+        let score = round(Goal::OneSafe, &[(1, 1)]).submit();
+        assert_eq!(
+            score,
+            Score {
+                correct: 0,
+                wrong: 1,
+                missed: 1
+            }
+        );
+
+        // Picking another cell replaces the guess
+        assert!(round(Goal::OneSafe, &[(1, 1), (2, 0)]).submit().passed());
+    }
+
+    #[test]
+    fn picks() {
+        // Revealed cells can't be picked, and picking a cell again unpicks it
+        let mut round = round(Goal::AllSafe, &[(0, 0), (2, 0), (2, 1), (2, 1)]);
+        assert_eq!(round.picks, [(2, 0)]);
+
+        // Nothing changes once the picks are submitted
+        round.submit();
+        let field = puzzle((3, 2), &[(0, 1)], &[(0, 0), (1, 0)]).field();
+        round.toggle_pick(&field, (2, 1));
+        assert_eq!(round.picks, [(2, 0)]);
+    }
+}
@@ -1,60 +1,134 @@
-use std::{iter, ops::Index};
+use std::iter;
 
 use itertools::Itertools;
-use ndarray::{array, azip, Array2};
+use ndarray::Array2;
 
-use crate::game::{neighbors, CellState, Field};
+use crate::game::{neighbors, CellState, Field, Rules};
 
-use self::bitvec_bitgrid::BitGrid;
+use self::bitgrid::{BitGrid, Grid};
+
+pub mod bitgrid;
+mod endgame;
+mod exact;
+mod linear;
+mod sat;
+
+use self::{exact::ExactSolver, sat::SatSolver};
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum Prediction {
-    Contradiction,
     Free,
     Mine,
-    Probability(f32, u8),
+    Probability(f32),
 }
 
 impl Prediction {
-    fn combine(self, other: Self) -> Self {
-        match (self, other) {
-            // Free and Mine is a Contradiction, and anything with a Contradiction is a Contradiction
-            (Self::Contradiction, _)
-            | (_, Self::Contradiction)
-            | (Self::Free, Self::Mine)
-            | (Self::Mine, Self::Free) => Self::Contradiction,
-            // Both Free or both Mine become that
-            result @ ((Self::Free, Self::Free) | (Self::Mine, Self::Mine)) => result.0,
-            // Free or Mine win out over a Probability
-            (result @ (Self::Free | Self::Mine), Self::Probability(..))
-            | (Self::Probability(..), result @ (Self::Free | Self::Mine)) => result,
-            // Average two Probabilities into a new Probability
-            (Self::Probability(p1, n1), Self::Probability(p2, n2)) => {
-                let n = n1 + n2;
-                Self::Probability((p1 * (n1 as f32) + p2 * (n2 as f32)) / n as f32, n)
-            }
+    pub(crate) fn from_probability(prob: f32) -> Self {
+        if prob == 0.0 {
+            Self::Free
+        } else if prob == 1.0 {
+            Self::Mine
+        } else {
+            Self::Probability(prob)
         }
     }
+}
 
-    pub(crate) fn from_probability(prob: f32) -> Self {
-        match prob {
-            p if p == 0.0 => Self::Free,
-            p if p == 1.0 => Self::Mine,
-            p => Self::Probability(p, 0),
+/// A way of working out which cells are mines.
+pub(crate) trait Solver {
+    /// Returns each cell's chance of being a mine, or None for cells it can't say anything about.
+    fn predict(&self, field: &Field) -> Array2<Option<f32>>;
+}
+
+/// Which [`Solver`] the app's predictions come from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum SolverChoice {
+    Regions,
+    Exact,
+    Sat,
+}
+
+impl SolverChoice {
+    pub(crate) const ALL: [Self; 3] = [Self::Regions, Self::Exact, Self::Sat];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Regions => "Regions",
+            Self::Exact => "Exact",
+            Self::Sat => "SAT",
         }
     }
+
+    /// Whether the solver can predict anything on a field with the given rules. Only the region
+    /// solver handles cells that hold several mines.
+    pub(crate) fn supports(self, rules: Rules) -> bool {
+        match self {
+            Self::Regions => true,
+            Self::Exact | Self::Sat => rules.mines_per_cell == 1,
+        }
+    }
+
+    pub(crate) fn solver(self) -> &'static dyn Solver {
+        match self {
+            Self::Regions => &RegionSolver,
+            Self::Exact => &ExactSolver,
+            Self::Sat => &SatSolver,
+        }
+    }
+}
+
+/// Splits the field into regions with known mine counts (see [`predict`]).
+pub(crate) struct RegionSolver;
+
+impl Solver for RegionSolver {
+    fn predict(&self, field: &Field) -> Array2<Option<f32>> {
+        predict(field)
+    }
 }
 
 pub(crate) fn predict(field: &Field) -> Array2<Option<f32>> {
-    let mut regions = iter::once(Region::from_field_unrevealed(field))
-        .chain(
-            field
-                .board
-                .indexed_iter()
-                .filter_map(|(pos, _)| Region::from_cell_revealed(field, pos)),
-        )
-        .collect::<Vec<_>>();
+    let mut predictions = predict_regions(
+        field,
+        iter::once(Region::from_field_unrevealed(field))
+            .chain(Region::all_from_cells_revealed(field))
+            .collect(),
+    );
+    linear::deduce(field, &mut predictions);
+    endgame::deduce(field, &mut predictions);
+    predictions
+}
+
+/// Like [`predict`], but only uses the constraints given by revealed numbers, ignoring the total
+/// number of mines.
+pub(crate) fn predict_local(field: &Field) -> Array2<Option<f32>> {
+    let mut predictions = predict_regions(field, Region::all_from_cells_revealed(field).collect());
+    linear::deduce(field, &mut predictions);
+    predictions
+}
 
+/// Only finds the cells that can be decided by looking at a single revealed number by itself (i.e.,
+/// its remaining mines are either zero, or equal to its unrevealed neighbors). All other cells are
+/// left as None.
+pub(crate) fn predict_single(field: &Field) -> Array2<Option<f32>> {
+    let mut predictions = Array2::<Option<f32>>::default(field.size());
+    for region in Region::all_from_cells_revealed(field) {
+        let probability = if region.is_clear() {
+            0.0
+        } else if region.is_full() {
+            1.0
+        } else {
+            continue;
+        };
+        for pos in region.region.indices() {
+            predictions[pos] = Some(probability);
+        }
+    }
+    predictions
+}
+
+fn predict_regions(field: &Field, mut regions: Vec<Region>) -> Array2<Option<f32>> {
+    // Regions without any cells can't tell us anything, but are expensive to keep checking
+    regions.retain(|region| region.size != 0);
     let mut predictions = Array2::<Option<Option<f32>>>::default(field.size());
 
     'outer: loop {
@@ -85,7 +159,7 @@ pub(crate) fn predict(field: &Field) -> Array2<Option<f32>> {
     }
 
     for region in regions {
-        let probability = region.mines as f32 / region.size as f32;
+        let probability = region.mines as f32 / region.capacity() as f32;
         for pos in region.region.indices() {
             match predictions[pos] {
                 Some(Some(prev_prob)) if prev_prob != probability => predictions[pos] = Some(None),
@@ -98,48 +172,113 @@ pub(crate) fn predict(field: &Field) -> Array2<Option<f32>> {
     predictions.mapv_into_any(Option::flatten)
 }
 
+/// How much a guaranteed opening is worth compared to merely surviving a guess.
+const OPENING_WEIGHT: f32 = 0.5;
+/// How much revealing a number next to undecided cells is worth compared to merely surviving a
+/// guess.
+const PROGRESS_WEIGHT: f32 = 0.25;
+
+/// Picks the unrevealed cell that is the best guess when no cell is known to be free. Returns None
+/// if there is a known free cell (so no guess is needed), or if there are no cells to guess.
+///
+/// Cells are ranked by their chance of being safe, weighted up by the chance of the guess being
+/// useful: either opening up an empty area, or revealing a number next to cells that are still
+/// undecided. Neighboring probabilities are treated as independent, which is not strictly true,
+/// but is good enough for ranking.
+pub(crate) fn recommend_guess(
+    field: &Field,
+    probabilities: &Array2<Option<f32>>,
+) -> Option<(usize, usize)> {
+    if probabilities.iter().any(|prob| *prob == Some(0.0)) {
+        return None;
+    }
+
+    probabilities
+        .indexed_iter()
+        .filter(|(pos, _)| field.board[*pos].state.is_unrevealed())
+        .filter_map(|(pos, prob)| prob.map(|prob| (pos, prob)))
+        .map(|(pos, prob)| {
+            let mut opening = 1.0;
+            let mut undecided = 0;
+            for neighbor_pos in neighbors(&field.board, pos) {
+                match (field.board[neighbor_pos].state, probabilities[neighbor_pos]) {
+                    (CellState::Flagged, _) => opening = 0.0,
+                    (CellState::Unrevealed | CellState::Questioned, Some(neighbor_prob)) => {
+                        opening *= 1.0 - neighbor_prob;
+                        if neighbor_prob > 0.0 && neighbor_prob < 1.0 {
+                            undecided += 1;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let progress = undecided as f32 / 8.0;
+
+            let score =
+                (1.0 - prob) * (1.0 + OPENING_WEIGHT * opening + PROGRESS_WEIGHT * progress);
+            (pos, score)
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(pos, _)| pos)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Region {
-    region: BitGrid,
+    region: Grid,
     size: usize,
     mines: usize,
+    /// The most mines each cell can hold
+    per_cell: usize,
 }
 
 impl Region {
-    fn empty(size: (usize, usize)) -> Self {
+    fn empty(size: (usize, usize), per_cell: usize) -> Self {
         Self {
-            region: BitGrid::empty(size),
+            region: Grid::empty(size),
             size: 0,
             mines: 0,
+            per_cell,
         }
     }
 
+    /// The most mines the region can hold.
+    fn capacity(&self) -> usize {
+        self.size * self.per_cell
+    }
+
     fn from_field_unrevealed(field: &Field) -> Self {
-        let region = BitGrid::empty(field.size()).with_indices(
+        let region = Grid::empty(field.size()).with_indices(
             field
                 .board
                 .indexed_iter()
-                .filter_map(|(pos, cell)| (cell.state == CellState::Unrevealed).then_some(pos)),
+                .filter_map(|(pos, cell)| (cell.state.is_unrevealed()).then_some(pos)),
         );
         let size = region.size();
         Self {
             region,
             size,
             mines: field.remaining_mines(),
+            per_cell: field.rules().mines_per_cell as usize,
         }
     }
 
+    /// Returns None if the cell isn't a revealed number, if it has more flags around it than its
+    /// number, or if the numbers can lie (so they don't give an exact count).
     fn from_cell_revealed(field: &Field, pos: (usize, usize)) -> Option<Self> {
-        if field.board[pos].state != CellState::Revealed {
+        if field.board[pos].state != CellState::Revealed || field.rules().liar {
             return None;
         }
 
-        let mut region = Self::empty(field.size());
-        region.mines = field.board[pos].neighbors as usize;
+        let mut region = Self::empty(field.size(), field.rules().mines_per_cell as usize);
+        region.mines = field.board[pos].number() as usize;
         for neighbor_pos in neighbors(&field.board, pos) {
-            match field.board[neighbor_pos].state {
-                CellState::Flagged => region.mines -= 1,
-                CellState::Unrevealed => {
+            let neighbor = field.board[neighbor_pos];
+            match neighbor.state {
+                // More flags than the number allows, so it can't tell us anything
+                CellState::Flagged | CellState::Exploded => {
+                    region.mines = region.mines.checked_sub(neighbor.marked_mines() as usize)?;
+                }
+                CellState::Unrevealed | CellState::Questioned => {
                     region.size += 1;
                     region.region.set(neighbor_pos, true);
                 }
@@ -150,26 +289,19 @@ impl Region {
         Some(region)
     }
 
+    fn all_from_cells_revealed(field: &Field) -> impl Iterator<Item = Self> + '_ {
+        field
+            .board
+            .indexed_iter()
+            .filter_map(|(pos, _)| Self::from_cell_revealed(field, pos))
+    }
+
     fn is_clear(&self) -> bool {
         self.mines == 0
     }
 
     fn is_full(&self) -> bool {
-        self.size == self.mines
-    }
-
-    fn merge_full(&self, other: &Self) -> Option<Self> {
-        if !self.is_full() || !other.is_full() {
-            return None;
-        }
-
-        let size = self.size + other.size - (&self.region & &other.region).size();
-
-        Some(Self {
-            region: &self.region | &other.region,
-            size,
-            mines: size,
-        })
+        self.capacity() == self.mines
     }
 
     fn split_overlap(&self, other: &Self) -> Option<[Self; 3]> {
@@ -182,16 +314,18 @@ impl Region {
         let a = self;
         let b = other;
 
-        let a_only = &a.region & &!&b.region;
-        let b_only = &b.region & &!&a.region;
-        let overlap = &a.region & &b.region;
+        let overlap = a.region.and(&b.region);
         let overlap_size = overlap.size();
         if overlap_size == 0 {
             return None;
         }
 
-        let a_range = a.mines.saturating_sub(a.size - overlap_size)..=a.mines.min(overlap_size);
-        let b_range = b.mines.saturating_sub(b.size - overlap_size)..=b.mines.min(overlap_size);
+        let per_cell = a.per_cell;
+        let overlap_capacity = overlap_size * per_cell;
+        let a_range =
+            a.mines.saturating_sub(a.capacity() - overlap_capacity)..=a.mines.min(overlap_capacity);
+        let b_range =
+            b.mines.saturating_sub(b.capacity() - overlap_capacity)..=b.mines.min(overlap_capacity);
         let overlap_mines = if a_range.start() == b_range.end() {
             *a_range.start()
         } else if a_range.end() == b_range.start() {
@@ -200,200 +334,314 @@ impl Region {
             return None;
         };
 
+        let a_only = a.region.and_not(&b.region);
+        let b_only = b.region.and_not(&a.region);
+
         Some([
             Self {
                 region: a_only,
                 size: a.size - overlap_size,
                 mines: a.mines - overlap_mines,
+                per_cell,
             },
             Self {
                 region: overlap,
                 size: overlap_size,
                 mines: overlap_mines,
+                per_cell,
             },
             Self {
                 region: b_only,
                 size: b.size - overlap_size,
                 mines: b.mines - overlap_mines,
+                per_cell,
             },
         ])
     }
 }
 
-mod ndarray_bitgrid {
-    use std::ops::{BitAnd, BitOr, Index, IndexMut, Not};
-
-    use ndarray::Array2;
-
-    #[derive(Debug, Clone, PartialEq, Eq)]
-    pub struct BitGrid(Array2<bool>);
-
-    impl BitGrid {
-        pub fn empty(size: (usize, usize)) -> Self {
-            Self(Array2::default(size))
-        }
-
-        pub fn size(&self) -> usize {
-            self.0.iter().filter(|c| **c).count()
-        }
-
-        pub fn set(&mut self, pos: (usize, usize), value: bool) {
-            self.0[pos] = value;
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*, sample::Index};
+
+    use super::*;
+
+    /// Builds a field from rows of cells: `.` is hidden and free, `*` is a hidden mine, `F` is a
+    /// flagged mine, `X` is an exploded mine, `?` is a free cell with a question mark, and `o` is
+    /// revealed (with its number worked out from the mines around it).
+    fn field(rows: &[&str]) -> Field {
+        let size = (rows[0].len(), rows.len());
+        let cell = |(x, y): (usize, usize)| rows[y].as_bytes()[x];
+        let positions = (0..size.0).cartesian_product(0..size.1);
+
+        let mines = positions
+            .clone()
+            .filter(|pos| matches!(cell(*pos), b'*' | b'F' | b'X'))
+            .collect::<Vec<_>>();
+        let mut field = Field::from_mines(size, &mines);
+        for pos in positions {
+            match cell(pos) {
+                b'F' => field.set_flags(pos, 1),
+                b'X' => field.board[pos].state = CellState::Exploded,
+                b'?' => field.board[pos].state = CellState::Questioned,
+                b'o' if field.board[pos].neighbors == 0 => {
+                    field.board[pos].state = CellState::Empty
+                }
+                b'o' => field.board[pos].state = CellState::Revealed,
+                _ => {}
+            }
         }
+        field
+    }
 
-        pub fn indices(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
-            self.0
-                .indexed_iter()
-                .filter_map(|(pos, c)| c.then_some(pos))
+    fn certain(prediction: Option<f32>) -> Option<bool> {
+        match prediction {
+            Some(0.0) => Some(false),
+            Some(1.0) => Some(true),
+            _ => None,
         }
+    }
 
-        pub fn with_indices(mut self, indices: impl Iterator<Item = (usize, usize)>) -> Self {
-            for pos in indices {
-                self.set(pos, true);
+    /// Asserts that the predictions decide every hidden cell, and match where the mines really are.
+    fn assert_solved(field: &Field, predictions: &Array2<Option<f32>>) {
+        for (pos, cell) in field.board.indexed_iter() {
+            if cell.state.is_unrevealed() {
+                assert_eq!(
+                    certain(predictions[pos]),
+                    Some(cell.has_mine()),
+                    "cell {pos:?}"
+                );
             }
-            self
         }
     }
 
-    impl Index<(usize, usize)> for BitGrid {
-        type Output = bool;
-
-        fn index(&self, index: (usize, usize)) -> &Self::Output {
-            &self.0[index]
+    fn region(size: (usize, usize), cells: &[(usize, usize)], mines: usize) -> Region {
+        Region {
+            region: Grid::empty(size).with_indices(cells.iter().copied()),
+            size: cells.len(),
+            mines,
+            per_cell: 1,
         }
     }
 
-    impl BitAnd<&BitGrid> for &BitGrid {
-        type Output = BitGrid;
+    #[test]
+    fn split_overlap() {
+        let size = (4, 1);
+        let a = region(size, &[(0, 0), (1, 0), (2, 0)], 2);
+        let b = region(size, &[(1, 0), (2, 0), (3, 0)], 1);
+        assert_eq!(
+            a.split_overlap(&b),
+            Some([
+                region(size, &[(0, 0)], 1),
+                region(size, &[(1, 0), (2, 0)], 1),
+                region(size, &[(3, 0)], 0),
+            ])
+        );
 
-        fn bitand(self, rhs: &BitGrid) -> Self::Output {
-            BitGrid(&self.0 & &rhs.0)
-        }
-    }
+        // One region inside the other
+        let size = (3, 1);
+        let a = region(size, &[(0, 0), (1, 0)], 1);
+        let b = region(size, &[(0, 0), (1, 0), (2, 0)], 2);
+        assert_eq!(
+            a.split_overlap(&b),
+            Some([
+                region(size, &[], 0),
+                region(size, &[(0, 0), (1, 0)], 1),
+                region(size, &[(2, 0)], 1),
+            ])
+        );
 
-    impl BitOr<&BitGrid> for &BitGrid {
-        type Output = BitGrid;
+        // The overlap could have a mine or not
+        let a = region(size, &[(0, 0), (1, 0)], 1);
+        let b = region(size, &[(1, 0), (2, 0)], 1);
+        assert_eq!(a.split_overlap(&b), None);
+    }
 
-        fn bitor(self, rhs: &BitGrid) -> Self::Output {
-            BitGrid(&self.0 | &rhs.0)
+    #[test]
+    fn one_two_one() {
+        let field = field(&[".*.*.", "ooooo", "ooooo"]);
+        assert_solved(&field, &predict_local(&field));
+        assert_solved(&field, &predict(&field));
+        for choice in SolverChoice::ALL {
+            assert_solved(&field, &choice.solver().predict(&field));
         }
     }
 
-    impl Not for &BitGrid {
-        type Output = BitGrid;
-
-        fn not(self) -> Self::Output {
-            BitGrid(!&self.0)
+    #[test]
+    fn one_two_two_one() {
+        let field = field(&["..**..", "oooooo", "oooooo"]);
+        assert_solved(&field, &predict_local(&field));
+        assert_solved(&field, &predict(&field));
+        for choice in SolverChoice::ALL {
+            assert_solved(&field, &choice.solver().predict(&field));
         }
     }
-}
-
-mod bitvec_bitgrid {
-    use std::ops::{BitAnd, BitOr, Index, IndexMut, Not};
 
-    use bitvec::vec::BitVec;
-
-    #[derive(Debug, Clone, PartialEq, Eq)]
-    pub struct BitGrid {
-        grid: BitVec,
-        stride: usize,
+    #[test]
+    fn question_marks() {
+        let field = field(&["?*.*?", "ooooo", "ooooo"]);
+        assert_solved(&field, &predict(&field));
+        for choice in SolverChoice::ALL {
+            assert_solved(&field, &choice.solver().predict(&field));
+        }
     }
 
-    impl BitGrid {
-        pub fn empty(size: (usize, usize)) -> Self {
-            BitGrid {
-                grid: bitvec::bitvec![0; size.0 * size.1],
-                stride: size.1,
-            }
-        }
+    #[test]
+    fn single_number() {
+        // The 1 next to the flag already has its mine
+        let field = field(&["...", "Foo", "ooo"]);
+        assert_solved(&field, &predict_single(&field));
 
-        pub fn size(&self) -> usize {
-            self.grid.count_ones()
-        }
+        // The 1s only have one hidden neighbor
+        let field = self::field(&["*o", "oo"]);
+        assert_solved(&field, &predict_single(&field));
+    }
 
-        pub fn set(&mut self, pos: (usize, usize), value: bool) {
-            self.grid.set(pos.0 * self.stride + pos.1, value);
-        }
+    #[test]
+    fn endgame_frontier() {
+        // Either the middle cell is a mine, or both outer cells are, but there is only one mine
+        let field = field(&[".o*o."]);
+        let local = predict_local(&field);
+        assert!(local.iter().all(|prob| certain(*prob).is_none()));
+        assert_solved(&field, &predict(&field));
+        assert_solved(&field, &SolverChoice::Exact.solver().predict(&field));
+    }
 
-        pub fn indices(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
-            self.grid
-                .iter_ones()
-                .map(|i| (i / self.stride, i % self.stride))
-        }
+    #[test]
+    fn endgame_interior() {
+        // The only mine must be next to the 1, so the far cell is free
+        let field = field(&["*o.."]);
+        let predictions = predict(&field);
+        assert_eq!(certain(predictions[(3, 0)]), Some(false));
+        assert_eq!(certain(predictions[(0, 0)]), None);
+        let exact = SolverChoice::Exact.solver().predict(&field);
+        assert_eq!(exact[(0, 0)], Some(0.5));
+        assert_eq!(exact[(3, 0)], Some(0.0));
+
+        // The 1 can only take one of the two mines, so the far cell is a mine
+        let field = self::field(&["*o.*"]);
+        let predictions = predict(&field);
+        assert_eq!(certain(predictions[(3, 0)]), Some(true));
+        assert_eq!(certain(predictions[(0, 0)]), None);
+    }
 
-        pub fn with_indices(mut self, indices: impl Iterator<Item = (usize, usize)>) -> Self {
-            for pos in indices {
-                self.set(pos, true);
+    #[test]
+    fn unsupported_rules() {
+        // Only the region solver can handle cells with several mines, the others predict nothing
+        let rules = Rules {
+            mines_per_cell: 2,
+            ..Rules::default()
+        };
+        let mut field = Field::new((9, 9), 20, rules).unwrap();
+        field.clear_cell((4, 4));
+        for choice in SolverChoice::ALL {
+            let supported = choice.supports(rules);
+            assert_eq!(supported, choice == SolverChoice::Regions);
+            if !supported {
+                let predictions = choice.solver().predict(&field);
+                assert!(predictions.iter().all(Option::is_none), "{}", choice.name());
             }
-            self
         }
     }
 
-    impl Index<(usize, usize)> for BitGrid {
-        type Output = bool;
-
-        fn index(&self, index: (usize, usize)) -> &Self::Output {
-            &self.grid[index.0 * self.stride + index.1]
+    #[test]
+    fn too_many_flags() {
+        // A wrong flag shouldn't make the solvers panic
+        let field = field(&["FF.", "ooo", "ooo"]);
+        let mut wrong = field.clone();
+        wrong.set_flags((2, 0), 1);
+        predict(&wrong);
+        predict_local(&wrong);
+        predict_single(&wrong);
+        for choice in SolverChoice::ALL {
+            choice.solver().predict(&wrong);
         }
     }
 
-    impl BitAnd<&BitGrid> for &BitGrid {
-        type Output = BitGrid;
-
-        fn bitand(self, rhs: &BitGrid) -> Self::Output {
-            BitGrid {
-                grid: self.grid.clone() & &rhs.grid,
-                stride: self.stride,
-            }
+    #[test]
+    fn exploded_mines() {
+        // The exploded mine counts towards the numbers, like a flag
+        let field = field(&["X..", "ooo"]);
+        assert_eq!(field.remaining_mines(), 0);
+        assert_solved(&field, &predict_single(&field));
+        for choice in SolverChoice::ALL {
+            assert_solved(&field, &choice.solver().predict(&field));
         }
     }
 
-    impl BitOr<&BitGrid> for &BitGrid {
-        type Output = BitGrid;
-
-        fn bitor(self, rhs: &BitGrid) -> Self::Output {
-            BitGrid {
-                grid: self.grid.clone() | &rhs.grid,
-                stride: self.stride,
-            }
-        }
+    /// A random board with at least one free cell, and the cell to click first.
+    fn board() -> impl Strategy<Value = (Field, (usize, usize))> {
+        (3usize..14, 3usize..14)
+            .prop_flat_map(|(width, height)| {
+                (
+                    Just((width, height)),
+                    vec(prop::bool::weighted(0.2), width * height),
+                    any::<Index>(),
+                )
+            })
+            .prop_filter_map("no free cells", |((width, height), mines, first)| {
+                let positions = (0..width).cartesian_product(0..height).collect::<Vec<_>>();
+                let (mines, free) = positions
+                    .into_iter()
+                    .zip(mines)
+                    .partition::<Vec<_>, _>(|(_, mine)| *mine);
+                let mines = mines.into_iter().map(|(pos, _)| pos).collect::<Vec<_>>();
+                let first = free.get(first.index(free.len().max(1)))?.0;
+                Some((Field::from_mines((width, height), &mines), first))
+            })
     }
 
-    impl Not for &BitGrid {
-        type Output = BitGrid;
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        /// Plays random boards out, checking that no solver ever marks a mine as free or a free
+        /// cell as a mine. When nothing is certain, a random free cell is revealed to carry on.
+        #[test]
+        fn predictions_are_correct((mut field, first) in board(), guesses in vec(any::<Index>(), 8)) {
+            field.clear_cell(first);
+            for guess in guesses {
+                let mut all = vec![predict(&field), predict_local(&field), predict_single(&field)];
+                all.extend(SolverChoice::ALL.map(|choice| choice.solver().predict(&field)));
+
+                for predictions in &all {
+                    for (pos, prob) in predictions.indexed_iter() {
+                        let cell = field.board[pos];
+                        if cell.state != CellState::Unrevealed {
+                            continue;
+                        }
+                        if let Some(prob) = prob {
+                            prop_assert!((0.0..=1.0).contains(prob));
+                        }
+                        if let Some(mine) = certain(*prob) {
+                            prop_assert_eq!(mine, cell.has_mine(), "cell {:?}", pos);
+                        }
+                    }
+                }
 
-        fn not(self) -> Self::Output {
-            BitGrid {
-                grid: self.grid.clone().not(),
-                stride: self.stride,
+                let mut progress = false;
+                for (pos, prob) in all[0].indexed_iter() {
+                    match certain(*prob) {
+                        Some(true) if field.board[pos].state == CellState::Unrevealed => {
+                            field.set_flags(pos, 1);
+                            progress = true;
+                        }
+                        Some(false) => progress |= field.clear_cell(pos).is_some(),
+                        _ => {}
+                    }
+                }
+                if !progress {
+                    let free = field
+                        .board
+                        .indexed_iter()
+                        .filter(|(_, cell)| cell.state.is_unrevealed() && !cell.has_mine())
+                        .map(|(pos, _)| pos)
+                        .collect::<Vec<_>>();
+                    if free.is_empty() {
+                        break;
+                    }
+                    field.clear_cell(free[guess.index(free.len())]);
+                }
             }
         }
     }
 }
-
-// #[test]
-// fn test_regions() {
-//     let test1 = Region {
-//         region: array![[true, true, true, false]],
-//         size: 3,
-//         mines: 2,
-//     }
-//     .split_overlap(&Region {
-//         region: array![[false, true, true, true]],
-//         size: 3,
-//         mines: 1,
-//     });
-//     dbg!(test1);
-//
-//     let test2 = Region {
-//         region: array![[true, true, false]],
-//         size: 2,
-//         mines: 2,
-//     }
-//     .split_overlap(&Region {
-//         region: array![[true, true, true]],
-//         size: 3,
-//         mines: 2,
-//     });
-//     dbg!(test2);
-// }
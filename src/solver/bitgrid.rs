@@ -0,0 +1,301 @@
+//! Sets of cells, used by the solver's regions. There are several implementations with the same
+//! API, so they can be benchmarked against each other (see `benches/bitgrid.rs`). The solver uses
+//! [`Grid`], which is picked with the `bitgrid-*` cargo features.
+
+use std::fmt::Debug;
+
+use bitvec::vec::BitVec;
+use ndarray::{Array2, Zip};
+
+/// The implementation used by the solver. Chunks of `u64` were the fastest in the benchmarks, so
+/// they are used unless another one is picked with a feature.
+#[cfg(feature = "bitgrid-ndarray")]
+pub type Grid = ArrayBitGrid;
+#[cfg(all(feature = "bitgrid-bitvec", not(feature = "bitgrid-ndarray")))]
+pub type Grid = BitVecBitGrid;
+#[cfg(not(any(feature = "bitgrid-ndarray", feature = "bitgrid-bitvec")))]
+pub type Grid = ChunkBitGrid;
+
+/// A set of cells on a board of a fixed size.
+pub trait BitGrid: Clone + Debug + PartialEq + Eq {
+    fn empty(size: (usize, usize)) -> Self;
+
+    /// The number of cells in the set.
+    fn size(&self) -> usize;
+
+    fn get(&self, pos: (usize, usize)) -> bool;
+
+    fn set(&mut self, pos: (usize, usize), value: bool);
+
+    fn indices(&self) -> Box<dyn Iterator<Item = (usize, usize)> + '_>;
+
+    /// The cells in both sets.
+    fn and(&self, other: &Self) -> Self;
+
+    /// The cells in either set.
+    fn or(&self, other: &Self) -> Self;
+
+    /// The cells in this set, but not the other.
+    fn and_not(&self, other: &Self) -> Self;
+
+    fn with_indices(mut self, indices: impl Iterator<Item = (usize, usize)>) -> Self {
+        for pos in indices {
+            self.set(pos, true);
+        }
+        self
+    }
+}
+
+/// A grid of `bool`s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayBitGrid(Array2<bool>);
+
+impl BitGrid for ArrayBitGrid {
+    fn empty(size: (usize, usize)) -> Self {
+        Self(Array2::default(size))
+    }
+
+    fn size(&self) -> usize {
+        self.0.iter().filter(|c| **c).count()
+    }
+
+    fn get(&self, pos: (usize, usize)) -> bool {
+        self.0[pos]
+    }
+
+    fn set(&mut self, pos: (usize, usize), value: bool) {
+        self.0[pos] = value;
+    }
+
+    fn indices(&self) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+        Box::new(
+            self.0
+                .indexed_iter()
+                .filter_map(|(pos, c)| c.then_some(pos)),
+        )
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        Self(&self.0 & &other.0)
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        Self(&self.0 | &other.0)
+    }
+
+    fn and_not(&self, other: &Self) -> Self {
+        Self(
+            Zip::from(&self.0)
+                .and(&other.0)
+                .map_collect(|a, b| *a && !*b),
+        )
+    }
+}
+
+/// A [`BitVec`] of the cells, one row after another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitVecBitGrid {
+    grid: BitVec,
+    stride: usize,
+}
+
+impl BitGrid for BitVecBitGrid {
+    fn empty(size: (usize, usize)) -> Self {
+        Self {
+            grid: bitvec::bitvec![0; size.0 * size.1],
+            stride: size.1,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.grid.count_ones()
+    }
+
+    fn get(&self, pos: (usize, usize)) -> bool {
+        self.grid[pos.0 * self.stride + pos.1]
+    }
+
+    fn set(&mut self, pos: (usize, usize), value: bool) {
+        self.grid.set(pos.0 * self.stride + pos.1, value);
+    }
+
+    fn indices(&self) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+        Box::new(
+            self.grid
+                .iter_ones()
+                .map(|i| (i / self.stride, i % self.stride)),
+        )
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        Self {
+            grid: self.grid.clone() & &other.grid,
+            stride: self.stride,
+        }
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        Self {
+            grid: self.grid.clone() | &other.grid,
+            stride: self.stride,
+        }
+    }
+
+    fn and_not(&self, other: &Self) -> Self {
+        Self {
+            grid: !other.grid.clone() & &self.grid,
+            stride: self.stride,
+        }
+    }
+}
+
+/// The cells packed into `u64`s, one row after another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkBitGrid {
+    chunks: Vec<u64>,
+    stride: usize,
+}
+
+impl ChunkBitGrid {
+    fn map(&self, other: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        Self {
+            chunks: self
+                .chunks
+                .iter()
+                .zip(&other.chunks)
+                .map(|(a, b)| f(*a, *b))
+                .collect(),
+            stride: self.stride,
+        }
+    }
+}
+
+impl BitGrid for ChunkBitGrid {
+    fn empty(size: (usize, usize)) -> Self {
+        Self {
+            chunks: vec![0; (size.0 * size.1 + 63) / 64],
+            stride: size.1,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.chunks
+            .iter()
+            .map(|chunk| chunk.count_ones() as usize)
+            .sum()
+    }
+
+    fn get(&self, pos: (usize, usize)) -> bool {
+        let i = pos.0 * self.stride + pos.1;
+        self.chunks[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn set(&mut self, pos: (usize, usize), value: bool) {
+        let i = pos.0 * self.stride + pos.1;
+        if value {
+            self.chunks[i / 64] |= 1 << (i % 64);
+        } else {
+            self.chunks[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    fn indices(&self) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+        Box::new(
+            self.chunks
+                .iter()
+                .enumerate()
+                .flat_map(|(chunk_i, chunk)| {
+                    let mut bits = *chunk;
+                    std::iter::from_fn(move || {
+                        (bits != 0).then(|| {
+                            let bit = bits.trailing_zeros() as usize;
+                            bits &= bits - 1;
+                            chunk_i * 64 + bit
+                        })
+                    })
+                })
+                .map(|i| (i / self.stride, i % self.stride)),
+        )
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        self.map(other, |a, b| a & b)
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        self.map(other, |a, b| a | b)
+    }
+
+    fn and_not(&self, other: &Self) -> Self {
+        self.map(other, |a, b| a & !b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::*;
+
+    /// The cells to set (or unset) on a grid, in order.
+    type Writes = Vec<((usize, usize), bool)>;
+
+    /// Everything that can be seen of a grid from outside: its size, every cell, and its indices.
+    type Observed = (usize, Vec<bool>, Vec<(usize, usize)>);
+
+    fn observe(grid: &impl BitGrid, (width, height): (usize, usize)) -> Observed {
+        let cells = (0..width)
+            .flat_map(|x| (0..height).map(move |y| (x, y)))
+            .map(|pos| grid.get(pos))
+            .collect();
+        (grid.size(), cells, grid.indices().collect())
+    }
+
+    /// Builds two grids from the writes, and observes every operation on them. A grid with every
+    /// cell set is included too, so that bits past the end of the board would be noticed.
+    fn run<G: BitGrid>(size: (usize, usize), a: &Writes, b: &Writes) -> Vec<Observed> {
+        let build = |writes: &Writes| {
+            let mut grid = G::empty(size);
+            for (pos, value) in writes {
+                grid.set(*pos, *value);
+            }
+            grid
+        };
+        let (a, b) = (build(a), build(b));
+        let full =
+            G::empty(size).with_indices((0..size.0).flat_map(|x| (0..size.1).map(move |y| (x, y))));
+
+        [
+            a.and(&b),
+            a.or(&b),
+            a.and_not(&b),
+            b.and_not(&a),
+            full.and_not(&a),
+            full.and(&b),
+            G::empty(size).with_indices(a.indices()),
+            full,
+            a,
+            b,
+        ]
+        .iter()
+        .map(|grid| observe(grid, size))
+        .collect()
+    }
+
+    /// Board sizes that are mostly not a multiple of 64 cells, with writes to two grids.
+    fn writes() -> impl Strategy<Value = ((usize, usize), Writes, Writes)> {
+        (1usize..12, 1usize..100).prop_flat_map(|size| {
+            let writes = vec(((0..size.0, 0..size.1), any::<bool>()), 0..300);
+            (Just(size), writes.clone(), writes)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn implementations_agree((size, a, b) in writes()) {
+            let expected = run::<ArrayBitGrid>(size, &a, &b);
+            prop_assert_eq!(&run::<BitVecBitGrid>(size, &a, &b), &expected);
+            prop_assert_eq!(&run::<ChunkBitGrid>(size, &a, &b), &expected);
+        }
+    }
+}
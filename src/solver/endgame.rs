@@ -0,0 +1,355 @@
+use std::ops::RangeInclusive;
+
+use ndarray::Array2;
+
+use crate::game::{neighbors, CellState, Field};
+
+/// Components with more cells than this are not enumerated.
+const MAX_COMPONENT_SIZE: usize = 48;
+/// How many assignments to try in a single component before giving up on it.
+const MAX_SEARCH_STEPS: usize = 200_000;
+
+/// A revealed number's constraint on its unrevealed neighbors: the number of mines in `cells` is
+/// within `mines`. This is a single count, unless the numbers can lie. Flagged neighbors are
+/// assumed to be mines, and are already subtracted along with exploded ones.
+#[derive(Debug, Clone)]
+pub(super) struct Constraint {
+    pub(super) cells: Vec<(usize, usize)>,
+    pub(super) mines: RangeInclusive<usize>,
+}
+
+/// Returns the constraints from every revealed number that has unrevealed neighbors. Returns None
+/// if a number has more flags around it than it allows.
+pub(super) fn constraints(field: &Field) -> Option<Vec<Constraint>> {
+    let mut constraints = Vec::new();
+    for (pos, cell) in field.board.indexed_iter() {
+        if cell.state != CellState::Revealed {
+            continue;
+        }
+
+        let mut cells = Vec::new();
+        let mut flags = 0;
+        for neighbor_pos in neighbors(&field.board, pos) {
+            match field.board[neighbor_pos].state {
+                CellState::Flagged | CellState::Exploded => {
+                    flags += field.board[neighbor_pos].marked_mines() as usize;
+                }
+                CellState::Unrevealed | CellState::Questioned => cells.push(neighbor_pos),
+                _ => {}
+            }
+        }
+        let (min, max) = field.mine_range(pos);
+        let max = max.checked_sub(flags)?;
+        if !cells.is_empty() {
+            constraints.push(Constraint {
+                cells,
+                mines: min.saturating_sub(flags)..=max,
+            });
+        }
+    }
+    Some(constraints)
+}
+
+/// A group of frontier cells that are linked together by shared constraints.
+#[derive(Debug)]
+pub(super) struct Component {
+    pub(super) cells: Vec<(usize, usize)>,
+    /// Each constraint as the indices into `cells` it covers, and how many mines it allows.
+    pub(super) constraints: Vec<(Vec<usize>, RangeInclusive<usize>)>,
+}
+
+/// Splits the frontier into components that can be solved independently.
+pub(super) fn components(field: &Field, constraints: &[Constraint]) -> Vec<Component> {
+    // Union-find over the constraints, joining any two that share a cell
+    let mut parent = (0..constraints.len()).collect::<Vec<_>>();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+
+    let mut owner = Array2::<Option<usize>>::default(field.size());
+    for (i, constraint) in constraints.iter().enumerate() {
+        for pos in &constraint.cells {
+            match owner[*pos] {
+                Some(other) => {
+                    let (a, b) = (find(&mut parent, i), find(&mut parent, other));
+                    parent[a] = b;
+                }
+                None => owner[*pos] = Some(i),
+            }
+        }
+    }
+
+    let mut index = Array2::<Option<usize>>::default(field.size());
+    let mut components = Vec::<Component>::new();
+    let mut component_of_root = vec![None; constraints.len()];
+    for (i, constraint) in constraints.iter().enumerate() {
+        let root = find(&mut parent, i);
+        let component_i = *component_of_root[root].get_or_insert_with(|| {
+            components.push(Component {
+                cells: Vec::new(),
+                constraints: Vec::new(),
+            });
+            components.len() - 1
+        });
+        let component = &mut components[component_i];
+
+        let indices = constraint
+            .cells
+            .iter()
+            .map(|pos| {
+                *index[*pos].get_or_insert_with(|| {
+                    component.cells.push(*pos);
+                    component.cells.len() - 1
+                })
+            })
+            .collect();
+        component
+            .constraints
+            .push((indices, constraint.mines.clone()));
+    }
+
+    components
+}
+
+/// Every way of placing mines in a component, grouped by how many mines are placed.
+#[derive(Debug)]
+pub(super) struct Solutions {
+    /// The number of solutions using each number of mines.
+    pub(super) counts: Vec<u64>,
+    /// For each number of mines, how many of those solutions have a mine in each cell.
+    pub(super) cell_mines: Vec<Vec<u64>>,
+}
+
+impl Component {
+    /// Enumerates every solution of this component. Returns None if the component is too large to
+    /// enumerate.
+    pub(super) fn solve(&self) -> Option<Solutions> {
+        if self.cells.len() > MAX_COMPONENT_SIZE {
+            return None;
+        }
+
+        let mut cell_constraints = vec![Vec::new(); self.cells.len()];
+        for (i, (indices, _)) in self.constraints.iter().enumerate() {
+            for cell in indices {
+                cell_constraints[*cell].push(i);
+            }
+        }
+
+        let mut search = Search {
+            component: self,
+            cell_constraints,
+            placed: vec![0; self.constraints.len()],
+            unassigned: self
+                .constraints
+                .iter()
+                .map(|(indices, _)| indices.len())
+                .collect(),
+            assignment: vec![false; self.cells.len()],
+            steps: 0,
+            solutions: Solutions {
+                counts: vec![0; self.cells.len() + 1],
+                cell_mines: vec![vec![0; self.cells.len()]; self.cells.len() + 1],
+            },
+        };
+        search.assign(0, 0).then_some(search.solutions)
+    }
+}
+
+struct Search<'a> {
+    component: &'a Component,
+    /// The constraints covering each cell.
+    cell_constraints: Vec<Vec<usize>>,
+    /// The number of mines placed so far in each constraint.
+    placed: Vec<usize>,
+    /// The number of cells not yet assigned in each constraint.
+    unassigned: Vec<usize>,
+    assignment: Vec<bool>,
+    steps: usize,
+    solutions: Solutions,
+}
+
+impl Search<'_> {
+    /// Tries both values for the given cell, then recurses into the next one. Returns false if the
+    /// search ran out of steps.
+    fn assign(&mut self, cell: usize, mines: usize) -> bool {
+        if cell == self.component.cells.len() {
+            self.solutions.counts[mines] += 1;
+            for (i, mine) in self.assignment.iter().enumerate() {
+                if *mine {
+                    self.solutions.cell_mines[mines][i] += 1;
+                }
+            }
+            return true;
+        }
+
+        for mine in [false, true] {
+            self.steps += 1;
+            if self.steps > MAX_SEARCH_STEPS {
+                return false;
+            }
+
+            self.assignment[cell] = mine;
+            let mut valid = true;
+            for &i in &self.cell_constraints[cell] {
+                self.unassigned[i] -= 1;
+                self.placed[i] += mine as usize;
+                let allowed = &self.component.constraints[i].1;
+                if self.placed[i] > *allowed.end()
+                    || self.placed[i] + self.unassigned[i] < *allowed.start()
+                {
+                    valid = false;
+                }
+            }
+
+            let completed = !valid || self.assign(cell + 1, mines + mine as usize);
+
+            for &i in &self.cell_constraints[cell] {
+                self.unassigned[i] += 1;
+                self.placed[i] -= mine as usize;
+            }
+            if !completed {
+                return false;
+            }
+        }
+
+        self.assignment[cell] = false;
+        true
+    }
+}
+
+/// Marks cells that are certain once the total number of remaining mines is taken into account
+/// alongside the frontier's constraints, e.g. when all of the remaining mines must be on the
+/// frontier, leaving every other cell free.
+pub(super) fn deduce(field: &Field, predictions: &mut Array2<Option<f32>>) {
+    // Enumeration only handles cells with at most one mine
+    if field.rules().mines_per_cell > 1 {
+        return;
+    }
+    let Some(constraints) = constraints(field) else {
+        return;
+    };
+    let components = components(field, &constraints);
+    let solutions = components.iter().map(Component::solve).collect::<Vec<_>>();
+
+    let remaining = field.remaining_mines();
+    let frontier = components
+        .iter()
+        .map(|component| component.cells.len())
+        .sum::<usize>();
+    let interior = field
+        .board
+        .iter()
+        .filter(|cell| cell.state.is_unrevealed())
+        .count()
+        - frontier;
+
+    // The mine counts each component could have by itself. Components that were too large to
+    // enumerate could have any count.
+    let possible = components
+        .iter()
+        .zip(&solutions)
+        .map(|(component, solutions)| match solutions {
+            Some(solutions) => solutions.counts.iter().map(|count| *count > 0).collect(),
+            None => vec![true; component.cells.len() + 1],
+        })
+        .collect::<Vec<Vec<bool>>>();
+
+    // The totals that could be reached by the components before (prefix) and after (suffix) each one
+    let mut prefix = vec![sums(&[true], &[true], remaining)];
+    for counts in &possible {
+        prefix.push(sums(prefix.last().unwrap(), counts, remaining));
+    }
+    let mut suffix = vec![sums(&[true], &[true], remaining)];
+    for counts in possible.iter().rev() {
+        suffix.push(sums(suffix.last().unwrap(), counts, remaining));
+    }
+    suffix.reverse();
+
+    // Whether a total number of mines on the frontier leaves a valid number for the interior
+    let fits = |frontier_mines: usize| {
+        frontier_mines <= remaining && remaining - frontier_mines <= interior
+    };
+
+    let interior_counts = prefix[components.len()]
+        .iter()
+        .enumerate()
+        .filter(|(total, possible)| **possible && fits(*total))
+        .map(|(total, _)| remaining - total)
+        .collect::<Vec<_>>();
+    let interior_prediction = if interior_counts.is_empty() {
+        // Contradiction, no way for the mines to fit
+        return;
+    } else if interior_counts.iter().all(|count| *count == 0) {
+        Some(0.0)
+    } else if interior_counts.iter().all(|count| *count == interior) {
+        Some(1.0)
+    } else {
+        None
+    };
+
+    let mut certain = Vec::new();
+    for (i, (component, solutions)) in components.iter().zip(&solutions).enumerate() {
+        let Some(solutions) = solutions else {
+            continue;
+        };
+        let others = sums(&prefix[i], &suffix[i + 1], remaining);
+        let feasible = (0..solutions.counts.len())
+            .filter(|k| {
+                solutions.counts[*k] > 0
+                    && others
+                        .iter()
+                        .enumerate()
+                        .any(|(total, possible)| *possible && fits(total + k))
+            })
+            .collect::<Vec<_>>();
+        if feasible.is_empty() {
+            return;
+        }
+
+        for (cell, pos) in component.cells.iter().enumerate() {
+            if feasible.iter().all(|k| solutions.cell_mines[*k][cell] == 0) {
+                certain.push((*pos, 0.0));
+            } else if feasible
+                .iter()
+                .all(|k| solutions.cell_mines[*k][cell] == solutions.counts[*k])
+            {
+                certain.push((*pos, 1.0));
+            }
+        }
+    }
+
+    for (pos, probability) in certain {
+        predictions[pos] = Some(probability);
+    }
+    if let Some(probability) = interior_prediction {
+        let on_frontier = constraints
+            .iter()
+            .flat_map(|constraint| &constraint.cells)
+            .copied()
+            .collect::<std::collections::HashSet<_>>();
+        for (pos, cell) in field.board.indexed_iter() {
+            if cell.state.is_unrevealed() && !on_frontier.contains(&pos) {
+                predictions[pos] = Some(probability);
+            }
+        }
+    }
+}
+
+/// Given which totals are possible for two groups, returns which totals (up to `max`) are possible
+/// for both groups together.
+fn sums(a: &[bool], b: &[bool], max: usize) -> Vec<bool> {
+    let mut result = vec![false; max + 1];
+    for (i, _) in a.iter().enumerate().filter(|(_, possible)| **possible) {
+        for (j, _) in b.iter().enumerate().filter(|(_, possible)| **possible) {
+            if let Some(total) = result.get_mut(i + j) {
+                *total = true;
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,167 @@
+use ndarray::Array2;
+
+use crate::game::Field;
+
+use super::{
+    endgame::{components, constraints, Component},
+    predict, Solver, SolverChoice,
+};
+
+/// Works out the exact chance of each cell being a mine, by enumerating every way the mines could
+/// be placed on the frontier and weighting each by how many ways the rest of the mines fit in the
+/// interior. Falls back to the region solver if the frontier is too large to enumerate. Cells can
+/// only hold one mine each: when the rules allow more, nothing is predicted.
+pub(crate) struct ExactSolver;
+
+impl Solver for ExactSolver {
+    fn predict(&self, field: &Field) -> Array2<Option<f32>> {
+        let mut predictions = Array2::<Option<f32>>::default(field.size());
+        if !SolverChoice::Exact.supports(field.rules()) {
+            return predictions;
+        }
+        let Some(constraints) = constraints(field) else {
+            return predictions;
+        };
+        let components = components(field, &constraints);
+        let Some(solutions) = components
+            .iter()
+            .map(Component::solve)
+            .collect::<Option<Vec<_>>>()
+        else {
+            return predict(field);
+        };
+
+        let remaining = field.remaining_mines();
+        let frontier = components
+            .iter()
+            .map(|component| component.cells.len())
+            .sum::<usize>();
+        let interior = field
+            .board
+            .iter()
+            .filter(|cell| cell.state.is_unrevealed())
+            .count()
+            - frontier;
+        // The (relative) number of ways to place each number of mines in the interior
+        let interior_ways = binomials(interior, remaining);
+        let interior_ways_for = |frontier_mines: usize| {
+            remaining
+                .checked_sub(frontier_mines)
+                .map_or(0.0, |mines| interior_ways[mines])
+        };
+
+        // The (relative) number of ways the components before (prefix) and after (suffix) each one
+        // can have each total number of mines
+        let counts = solutions
+            .iter()
+            .map(|solutions| solutions.counts.iter().map(|count| *count as f64).collect())
+            .collect::<Vec<Vec<f64>>>();
+        let mut prefix = vec![vec![1.0]];
+        for counts in &counts {
+            prefix.push(convolve(prefix.last().unwrap(), counts));
+        }
+        let mut suffix = vec![vec![1.0]];
+        for counts in counts.iter().rev() {
+            suffix.push(convolve(suffix.last().unwrap(), counts));
+        }
+        suffix.reverse();
+
+        for (i, (component, solutions)) in components.iter().zip(&solutions).enumerate() {
+            let others = convolve(&prefix[i], &suffix[i + 1]);
+            // The weight of each of this component's solutions with the given number of mines
+            let weights = (0..solutions.counts.len())
+                .map(|mines| {
+                    others
+                        .iter()
+                        .enumerate()
+                        .map(|(other_mines, ways)| ways * interior_ways_for(mines + other_mines))
+                        .sum::<f64>()
+                })
+                .collect::<Vec<_>>();
+            let total = solutions
+                .counts
+                .iter()
+                .zip(&weights)
+                .map(|(count, weight)| *count as f64 * weight)
+                .sum::<f64>();
+            if total == 0.0 {
+                // No way for the mines to fit
+                return Array2::default(field.size());
+            }
+
+            for (cell, pos) in component.cells.iter().enumerate() {
+                let with_mine = solutions
+                    .cell_mines
+                    .iter()
+                    .zip(&weights)
+                    .map(|(cell_mines, weight)| cell_mines[cell] as f64 * weight)
+                    .sum::<f64>();
+                predictions[*pos] = Some(probability(with_mine, total));
+            }
+        }
+
+        if interior > 0 {
+            let (mut total, mut mines) = (0.0, 0.0);
+            for (frontier_mines, ways) in prefix[components.len()].iter().enumerate() {
+                let weight = ways * interior_ways_for(frontier_mines);
+                total += weight;
+                mines += weight * remaining.saturating_sub(frontier_mines) as f64 / interior as f64;
+            }
+            if total > 0.0 {
+                let probability = probability(mines, total);
+                for (pos, cell) in field.board.indexed_iter() {
+                    if cell.state.is_unrevealed() && predictions[pos].is_none() {
+                        predictions[pos] = Some(probability);
+                    }
+                }
+            }
+        }
+
+        predictions
+    }
+}
+
+/// The number of ways to choose each number of items from `n`, up to `max`, scaled so that the
+/// largest is 1 (the real numbers can be too large for a float).
+fn binomials(n: usize, max: usize) -> Vec<f64> {
+    let mut logs = vec![f64::NEG_INFINITY; max + 1];
+    let mut log = 0.0;
+    for (k, value) in logs.iter_mut().enumerate().take(n + 1) {
+        if k > 0 {
+            log += ((n - k + 1) as f64).ln() - (k as f64).ln();
+        }
+        *value = log;
+    }
+    let largest = logs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    logs.into_iter().map(|log| (log - largest).exp()).collect()
+}
+
+/// Combines the numbers of ways two groups can have each number of mines, scaled so that the
+/// largest is 1.
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            result[i + j] += a * b;
+        }
+    }
+    let largest = result.iter().copied().fold(0.0, f64::max);
+    if largest > 0.0 {
+        for value in &mut result {
+            *value /= largest;
+        }
+    }
+    result
+}
+
+/// Divides two weights into a probability, making sure that only certain results round to exactly
+/// 0 or 1, since those are treated as known.
+fn probability(part: f64, total: f64) -> f32 {
+    if part == 0.0 {
+        0.0
+    } else if part == total {
+        1.0
+    } else {
+        ((part / total) as f32).clamp(f32::MIN_POSITIVE, 1.0 - f32::EPSILON)
+    }
+}
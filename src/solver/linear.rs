@@ -0,0 +1,138 @@
+use ndarray::Array2;
+
+use crate::game::Field;
+
+use super::endgame::{components, constraints, Component};
+
+/// Marks the cells that are forced by the revealed numbers, found by treating the numbers as a
+/// system of linear equations over the frontier cells and reducing it with Gaussian elimination.
+/// This finds deductions that need more than two numbers to be combined, without enumerating every
+/// solution. Numbers that only give a range of counts (in liar mode) are left out.
+pub(super) fn deduce(field: &Field, predictions: &mut Array2<Option<f32>>) {
+    let Some(constraints) = constraints(field) else {
+        return;
+    };
+
+    let per_cell = field.rules().mines_per_cell as i64;
+    for component in components(field, &constraints) {
+        let mut known = vec![None; component.cells.len()];
+        while find_forced(&component, per_cell, &mut known) {}
+
+        for (pos, mines) in component.cells.iter().zip(known) {
+            if let Some(mines) = mines {
+                predictions[*pos] = Some(mines as f32 / per_cell as f32);
+            }
+        }
+    }
+}
+
+/// Reduces the component's constraints, with the already known cells substituted in, and records
+/// every cell that a reduced row forces. Each cell holds from 0 to `per_cell` mines. Returns
+/// whether any new cells were found.
+fn find_forced(component: &Component, per_cell: i64, known: &mut [Option<i64>]) -> bool {
+    let width = known.len();
+    // Each row is the coefficients of every cell, followed by the number of mines
+    let mut rows = component
+        .constraints
+        .iter()
+        .filter(|(_, mines)| mines.start() == mines.end())
+        .map(|(indices, mines)| {
+            let mut row = vec![0; width + 1];
+            row[width] = *mines.start() as i64;
+            for &i in indices {
+                match known[i] {
+                    Some(mines) => row[width] -= mines,
+                    None => row[i] = 1,
+                }
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+    if !reduce(&mut rows, width) {
+        return false;
+    }
+
+    let mut found = false;
+    for row in &rows {
+        let (coefficients, target) = (&row[..width], row[width]);
+        let min = coefficients.iter().filter(|c| **c < 0).sum::<i64>() * per_cell;
+        let max = coefficients.iter().filter(|c| **c > 0).sum::<i64>() * per_cell;
+        // Since every cell is between empty and full, a row can only reach its smallest or largest
+        // total in one way, which decides every cell in it
+        let full_sign = if min == max {
+            continue;
+        } else if target == max {
+            1
+        } else if target == min {
+            -1
+        } else {
+            continue;
+        };
+
+        for (i, coefficient) in coefficients.iter().enumerate() {
+            if *coefficient != 0 && known[i].is_none() {
+                known[i] = Some(if coefficient.signum() == full_sign {
+                    per_cell
+                } else {
+                    0
+                });
+                found = true;
+            }
+        }
+    }
+    found
+}
+
+/// Puts the rows into reduced row echelon form. Entries are kept as integers by scaling rows
+/// instead of dividing them. The last column is the right-hand side, and is never used as a pivot.
+/// Returns false if the entries grew too large.
+fn reduce(rows: &mut [Vec<i64>], width: usize) -> bool {
+    let mut pivot_i = 0;
+    for column in 0..width {
+        if pivot_i == rows.len() {
+            break;
+        }
+        let Some(found) = (pivot_i..rows.len()).find(|i| rows[*i][column] != 0) else {
+            continue;
+        };
+        rows.swap(pivot_i, found);
+
+        let pivot = rows[pivot_i].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            let factor = row[column];
+            if i == pivot_i || factor == 0 {
+                continue;
+            }
+            for (value, pivot_value) in row.iter_mut().zip(&pivot) {
+                let Some(new_value) = value
+                    .checked_mul(pivot[column])
+                    .zip(pivot_value.checked_mul(factor))
+                    .and_then(|(a, b)| a.checked_sub(b))
+                else {
+                    return false;
+                };
+                *value = new_value;
+            }
+            normalize(row);
+        }
+        pivot_i += 1;
+    }
+    true
+}
+
+/// Divides a row by the greatest common divisor of its entries, to keep them small.
+fn normalize(row: &mut [i64]) {
+    let divisor = row.iter().fold(0, |a, b| gcd(a, b.abs()));
+    if divisor > 1 {
+        for value in row {
+            *value /= divisor;
+        }
+    }
+}
+
+fn gcd(mut a: i64, mut b: i64) -> i64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
@@ -0,0 +1,210 @@
+use std::ops::RangeInclusive;
+
+use itertools::Itertools;
+use ndarray::Array2;
+
+use crate::game::Field;
+
+use super::{
+    endgame::{components, constraints},
+    Solver, SolverChoice,
+};
+
+/// How many decisions a single satisfiability check can make before giving up.
+const MAX_DECISIONS: usize = 10_000;
+
+/// Proves frontier cells safe or mined by encoding the revealed numbers as a boolean formula, and
+/// checking whether the formula can still be satisfied with each cell set the other way. Only the
+/// revealed numbers are encoded, so unlike the region solver, the total mine count is not used.
+/// Cells can only hold one mine each: when the rules allow more, nothing is predicted.
+pub(crate) struct SatSolver;
+
+impl Solver for SatSolver {
+    fn predict(&self, field: &Field) -> Array2<Option<f32>> {
+        let mut predictions = Array2::<Option<f32>>::default(field.size());
+        if !SolverChoice::Sat.supports(field.rules()) {
+            return predictions;
+        }
+        let Some(constraints) = constraints(field) else {
+            return predictions;
+        };
+
+        for component in components(field, &constraints) {
+            let mut formula = Formula::new(component.cells.len());
+            for (indices, mines) in &component.constraints {
+                formula.add_between(indices, mines.clone());
+            }
+
+            // Which values each cell has taken in any model found so far, indexed by the value
+            let mut seen = [vec![false; formula.vars], vec![false; formula.vars]];
+            fn record(seen: &mut [Vec<bool>; 2], model: &[bool]) {
+                for (var, value) in model.iter().enumerate() {
+                    seen[*value as usize][var] = true;
+                }
+            }
+            match formula.solve(&[]) {
+                Outcome::Sat(model) => record(&mut seen, &model),
+                Outcome::Unsat | Outcome::Unknown => continue,
+            }
+
+            for (var, pos) in component.cells.iter().enumerate() {
+                let mut proven = None;
+                for value in [false, true] {
+                    if seen[value as usize][var] {
+                        continue;
+                    }
+                    match formula.solve(&[(var, value)]) {
+                        Outcome::Sat(model) => record(&mut seen, &model),
+                        Outcome::Unsat => proven = Some(!value),
+                        Outcome::Unknown => {}
+                    }
+                }
+                if let Some(mine) = proven {
+                    predictions[*pos] = Some(if mine { 1.0 } else { 0.0 });
+                }
+            }
+        }
+
+        predictions
+    }
+}
+
+/// A literal, as a variable and the value that satisfies it.
+type Literal = (usize, bool);
+
+enum Outcome {
+    Sat(Vec<bool>),
+    Unsat,
+    /// The check ran out of decisions.
+    Unknown,
+}
+
+/// A boolean formula in conjunctive normal form.
+struct Formula {
+    vars: usize,
+    clauses: Vec<Vec<Literal>>,
+}
+
+impl Formula {
+    fn new(vars: usize) -> Self {
+        Self {
+            vars,
+            clauses: Vec::new(),
+        }
+    }
+
+    /// Adds clauses requiring the number of the given variables that are true to be within `count`.
+    /// Every group of `max + 1` variables must have a false one, and every group of
+    /// `len - min + 1` must have a true one. This grows quickly, but revealed numbers only ever
+    /// cover 8 cells.
+    fn add_between(&mut self, vars: &[usize], count: RangeInclusive<usize>) {
+        let (min, max) = count.into_inner();
+        if min > vars.len() {
+            self.clauses.push(Vec::new());
+            return;
+        }
+        for group in vars.iter().combinations(max + 1) {
+            self.clauses
+                .push(group.into_iter().map(|var| (*var, false)).collect());
+        }
+        for group in vars.iter().combinations(vars.len() - min + 1) {
+            self.clauses
+                .push(group.into_iter().map(|var| (*var, true)).collect());
+        }
+    }
+
+    /// Looks for an assignment satisfying every clause and the given assumptions.
+    fn solve(&self, assumptions: &[Literal]) -> Outcome {
+        let mut assignment = vec![None; self.vars];
+        for (var, value) in assumptions {
+            assignment[*var] = Some(*value);
+        }
+        let mut decisions = 0;
+        match self.search(&mut assignment, &mut decisions) {
+            Some(true) => Outcome::Sat(
+                assignment
+                    .into_iter()
+                    .map(|value| value.unwrap_or(false))
+                    .collect(),
+            ),
+            Some(false) => Outcome::Unsat,
+            None => Outcome::Unknown,
+        }
+    }
+
+    /// DPLL: propagates unit clauses, then tries both values of the first unassigned variable.
+    /// Returns whether the assignment could be completed, or None if it ran out of decisions. On
+    /// success, the assignment is left complete.
+    fn search(&self, assignment: &mut [Option<bool>], decisions: &mut usize) -> Option<bool> {
+        let mut trail = Vec::new();
+        let result = if !self.propagate(assignment, &mut trail) {
+            Some(false)
+        } else if let Some(var) = assignment.iter().position(Option::is_none) {
+            let mut result = Some(false);
+            for value in [false, true] {
+                *decisions += 1;
+                if *decisions > MAX_DECISIONS {
+                    return None;
+                }
+                assignment[var] = Some(value);
+                result = self.search(assignment, decisions);
+                if result != Some(false) {
+                    break;
+                }
+            }
+            if result == Some(false) {
+                assignment[var] = None;
+            }
+            result
+        } else {
+            Some(true)
+        };
+
+        if result == Some(false) {
+            for var in trail {
+                assignment[var] = None;
+            }
+        }
+        result
+    }
+
+    /// Assigns the last literal of every clause that has only one left, until there are none.
+    /// Returns false if a clause can no longer be satisfied.
+    fn propagate(&self, assignment: &mut [Option<bool>], trail: &mut Vec<usize>) -> bool {
+        loop {
+            let mut changed = false;
+            for clause in &self.clauses {
+                let mut unassigned = None;
+                let mut unassigned_count = 0;
+                let mut satisfied = false;
+                for (var, value) in clause {
+                    match assignment[*var] {
+                        Some(assigned) if assigned == *value => {
+                            satisfied = true;
+                            break;
+                        }
+                        Some(_) => {}
+                        None => {
+                            unassigned = Some((*var, *value));
+                            unassigned_count += 1;
+                        }
+                    }
+                }
+
+                match (satisfied, unassigned_count, unassigned) {
+                    (true, _, _) => {}
+                    (false, 0, _) => return false,
+                    (false, 1, Some((var, value))) => {
+                        assignment[var] = Some(value);
+                        trail.push(var);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            if !changed {
+                return true;
+            }
+        }
+    }
+}
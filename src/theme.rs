@@ -0,0 +1,340 @@
+use egui::{
+    emath::Rot2, lerp, vec2, Align2, Color32, FontId, Painter, Pos2, Rect, Rounding, Shape, Stroke,
+    Vec2, Visuals,
+};
+
+use crate::solver::Prediction;
+
+/// Points along the viridis colormap, which is perceptually uniform and readable with any kind of
+/// colorblindness.
+const VIRIDIS: [(u8, u8, u8); 5] = [
+    (68, 1, 84),
+    (59, 82, 139),
+    (33, 145, 140),
+    (94, 201, 98),
+    (253, 231, 37),
+];
+/// The most hatching lines drawn for a probability of 1.
+const MAX_HATCH_LINES: f32 = 6.0;
+
+/// Which theme the field is drawn with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum ThemeChoice {
+    /// Follows the egui light/dark visuals.
+    Egui,
+    /// Looks like the classic Windows game.
+    Classic,
+    /// The user's own colors.
+    Custom,
+}
+
+impl ThemeChoice {
+    pub(crate) const ALL: [Self; 3] = [Self::Egui, Self::Classic, Self::Custom];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Egui => "Default",
+            Self::Classic => "Classic",
+            Self::Custom => "Custom",
+        }
+    }
+}
+
+/// How solver predictions are drawn over unrevealed cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum OverlayStyle {
+    /// Green for free, red for mines, and yellow shades for probabilities.
+    Colors,
+    /// Shades from the viridis colormap, from purple (free) to yellow (mine).
+    Viridis,
+    /// The chance of a mine as a percentage.
+    Percentages,
+    /// A circle for free, a cross for mines, and denser hatching for higher probabilities.
+    Patterns,
+}
+
+impl OverlayStyle {
+    pub(crate) const ALL: [Self; 4] = [
+        Self::Colors,
+        Self::Viridis,
+        Self::Percentages,
+        Self::Patterns,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Colors => "Colors",
+            Self::Viridis => "Viridis",
+            Self::Percentages => "Percentages",
+            Self::Patterns => "Patterns",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum TileStyle {
+    /// Outlined tiles, with explosions drawn as a starburst.
+    Flat,
+    /// Beveled tiles, with explosions drawn as a mine.
+    Raised,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Theme {
+    pub(crate) tiles: TileStyle,
+    pub(crate) hidden_fill: Color32,
+    pub(crate) revealed_fill: Color32,
+    pub(crate) exploded_fill: Color32,
+    pub(crate) border: Color32,
+    pub(crate) text: Color32,
+    /// The colors of the numbers 1 through 8.
+    pub(crate) numbers: [Color32; 8],
+    pub(crate) flag: Color32,
+    pub(crate) mine: Color32,
+    pub(crate) selected: Color32,
+    pub(crate) hovered: Color32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::from_visuals(&Visuals::default())
+    }
+}
+
+impl Theme {
+    /// The theme matching the given egui visuals.
+    pub(crate) fn from_visuals(visuals: &Visuals) -> Self {
+        let text = visuals.strong_text_color();
+        Self {
+            tiles: TileStyle::Flat,
+            hidden_fill: visuals.window_fill(),
+            revealed_fill: visuals.window_fill(),
+            exploded_fill: visuals.window_fill(),
+            border: visuals.window_stroke().color,
+            text,
+            numbers: [text; 8],
+            flag: text,
+            mine: text,
+            selected: Color32::BLUE,
+            hovered: Color32::LIGHT_BLUE,
+        }
+    }
+
+    pub(crate) fn classic() -> Self {
+        Self {
+            tiles: TileStyle::Raised,
+            hidden_fill: Color32::from_gray(192),
+            revealed_fill: Color32::from_gray(189),
+            exploded_fill: Color32::RED,
+            border: Color32::from_gray(128),
+            text: Color32::BLACK,
+            numbers: [
+                Color32::from_rgb(0, 0, 255),
+                Color32::from_rgb(0, 128, 0),
+                Color32::from_rgb(255, 0, 0),
+                Color32::from_rgb(0, 0, 128),
+                Color32::from_rgb(128, 0, 0),
+                Color32::from_rgb(0, 128, 128),
+                Color32::BLACK,
+                Color32::from_gray(128),
+            ],
+            flag: Color32::RED,
+            mine: Color32::BLACK,
+            selected: Color32::BLUE,
+            hovered: Color32::LIGHT_BLUE,
+        }
+    }
+
+    pub(crate) fn number_color(&self, number: u8) -> Color32 {
+        number
+            .checked_sub(1)
+            .and_then(|i| self.numbers.get(i as usize))
+            .copied()
+            .unwrap_or(self.text)
+    }
+}
+
+/// Draws the light and dark edges that make a tile look raised.
+pub(crate) fn paint_raised(painter: &Painter, rect: Rect, width: f32) {
+    let light = Color32::WHITE;
+    let dark = Color32::from_gray(128);
+    let inner = rect.shrink(width);
+    let edge = |corners: [Pos2; 4], color| {
+        painter.add(Shape::convex_polygon(corners.to_vec(), color, Stroke::NONE));
+    };
+
+    edge(
+        [
+            rect.left_top(),
+            rect.right_top(),
+            inner.right_top(),
+            inner.left_top(),
+        ],
+        light,
+    );
+    edge(
+        [
+            rect.left_top(),
+            inner.left_top(),
+            inner.left_bottom(),
+            rect.left_bottom(),
+        ],
+        light,
+    );
+    edge(
+        [
+            rect.right_bottom(),
+            rect.left_bottom(),
+            inner.left_bottom(),
+            inner.right_bottom(),
+        ],
+        dark,
+    );
+    edge(
+        [
+            rect.right_bottom(),
+            inner.right_bottom(),
+            inner.right_top(),
+            rect.right_top(),
+        ],
+        dark,
+    );
+}
+
+pub(crate) fn paint_flag(painter: &Painter, rect: Rect, pole: Stroke, flag: Color32) {
+    let flag_rect = rect.shrink2(rect.size() / vec2(3.0, 4.0));
+    painter.line_segment([flag_rect.left_top(), flag_rect.left_bottom()], pole);
+    painter.rect_filled(
+        flag_rect.with_max_y(flag_rect.center().y),
+        Rounding::ZERO,
+        flag,
+    );
+}
+
+pub(crate) fn paint_mine(painter: &Painter, rect: Rect, color: Color32) {
+    let radius = rect.height() / 4.0;
+    let stroke = Stroke::new(rect.height() / 16.0, color);
+    for i in 0..4 {
+        let spike =
+            Rot2::from_angle(i as f32 / 8.0 * std::f32::consts::TAU) * vec2(radius * 1.4, 0.0);
+        painter.line_segment([rect.center() - spike, rect.center() + spike], stroke);
+    }
+    painter.circle_filled(rect.center(), radius, color);
+    painter.circle_filled(
+        rect.center() - Vec2::splat(radius / 3.0),
+        radius / 4.0,
+        Color32::WHITE,
+    );
+}
+
+/// A flag with a cross through it, for flags that turned out not to be on a mine.
+pub(crate) fn paint_wrong_flag(painter: &Painter, rect: Rect, pole: Stroke, flag: Color32) {
+    paint_flag(painter, rect, pole, flag);
+    let cross = rect.shrink(rect.height() / 6.0);
+    let stroke = Stroke::new(rect.height() / 12.0, Color32::RED);
+    painter.line_segment([cross.left_top(), cross.right_bottom()], stroke);
+    painter.line_segment([cross.right_top(), cross.left_bottom()], stroke);
+}
+
+pub(crate) fn paint_starburst(painter: &Painter, rect: Rect) {
+    let draw_starburst = |radius, color| {
+        let outer_rad = vec2(0.0, radius);
+        let inner_rad = outer_rad * 0.75;
+
+        let points = (0..=16)
+            .map(|r| {
+                let v = if r % 2 == 0 { inner_rad } else { outer_rad };
+                let rot = Rot2::from_angle(r as f32 / -16.0 * std::f32::consts::TAU);
+                rect.center() + (rot * v)
+            })
+            .collect();
+        painter.add(Shape::convex_polygon(points, color, Stroke::NONE));
+    };
+
+    draw_starburst(rect.height() / 2.5, Color32::YELLOW);
+    draw_starburst(rect.height() / 4.0, Color32::LIGHT_RED);
+}
+
+/// Draws a prediction over an unrevealed cell, in the given overlay style.
+pub(crate) fn paint_prediction(
+    painter: &Painter,
+    rect: Rect,
+    prediction: Prediction,
+    style: OverlayStyle,
+    text_color: Color32,
+) {
+    let inner = rect.shrink(rect.height() / 5.0);
+    let stroke = Stroke::new(rect.height() / 12.0, text_color);
+
+    match style {
+        OverlayStyle::Colors => {
+            let color = match prediction {
+                Prediction::Free => Color32::GREEN,
+                Prediction::Mine => Color32::RED,
+                Prediction::Probability(prob) => Color32::YELLOW.gamma_multiply(prob),
+            };
+            painter.rect_filled(inner, Rounding::ZERO, color);
+        }
+        OverlayStyle::Viridis => {
+            let prob = match prediction {
+                Prediction::Free => 0.0,
+                Prediction::Mine => 1.0,
+                Prediction::Probability(prob) => prob,
+            };
+            painter.rect_filled(inner, Rounding::ZERO, viridis(prob));
+        }
+        OverlayStyle::Percentages => {
+            let text = match prediction {
+                Prediction::Free => "0".to_owned(),
+                Prediction::Mine => "100".to_owned(),
+                // Only known cells should show 0 or 100, however close an undecided one is
+                Prediction::Probability(prob) => {
+                    format!("{:.0}", (prob * 100.0).round().clamp(1.0, 99.0))
+                }
+            };
+            painter.text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                text,
+                FontId::proportional(rect.height() * 0.4),
+                text_color,
+            );
+        }
+        OverlayStyle::Patterns => match prediction {
+            Prediction::Free => {
+                painter.circle_stroke(inner.center(), inner.height() / 2.0, stroke);
+            }
+            Prediction::Mine => paint_cross(painter, inner, stroke),
+            Prediction::Probability(prob) => {
+                // Diagonal lines (x + y = c) across the inner square, evenly spaced
+                let lines = (prob * MAX_HATCH_LINES).ceil() as usize;
+                let side = inner.width();
+                let stroke = Stroke::new(stroke.width / 2.0, text_color);
+                for i in 1..=lines {
+                    let c = 2.0 * side * i as f32 / (lines + 1) as f32;
+                    let (low, high) = ((c - side).max(0.0), c.min(side));
+                    painter.line_segment(
+                        [inner.min + vec2(low, high), inner.min + vec2(high, low)],
+                        stroke,
+                    );
+                }
+            }
+        },
+    }
+}
+
+fn paint_cross(painter: &Painter, rect: Rect, stroke: Stroke) {
+    painter.line_segment([rect.left_top(), rect.right_bottom()], stroke);
+    painter.line_segment([rect.right_top(), rect.left_bottom()], stroke);
+}
+
+/// Samples the viridis colormap at `t` (from 0 to 1).
+fn viridis(t: f32) -> Color32 {
+    let scaled = t.clamp(0.0, 1.0) * (VIRIDIS.len() - 1) as f32;
+    let i = (scaled.floor() as usize).min(VIRIDIS.len() - 2);
+    let frac = scaled - i as f32;
+    let ((r1, g1, b1), (r2, g2, b2)) = (VIRIDIS[i], VIRIDIS[i + 1]);
+    let channel = |a: u8, b: u8| lerp(a as f32..=b as f32, frac).round() as u8;
+    Color32::from_rgb(channel(r1, r2), channel(g1, g2), channel(b1, b2))
+}